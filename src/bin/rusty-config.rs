@@ -0,0 +1,291 @@
+//! `rusty-config`: inspect, convert and edit config files from the shell
+//!
+//! ```text
+//! rusty-config validate config.yaml --schema config.schema.json
+//! rusty-config convert --strict config.yaml config.toml
+//! rusty-config get config.yaml server.port
+//! rusty-config set config.yaml server.port=9090 debug=true
+//! rusty-config diff config.yaml config.json
+//! rusty-config fmt --check config.yaml   # --force also formats files with comments
+//! rusty-config merge base.yaml overlay.yaml -o merged.yaml
+//! ```
+//!
+//! Exits with 0 on success, 1 if a check fails (invalid file, differences,
+//! unformatted file or one with comments, missing key, lossy conversion)
+//! and 2 on errors.
+
+use clap::{Parser, Subcommand};
+use rusty_config::cli::parse_override;
+use rusty_config::loader::{self, ConfigFormat};
+use rusty_config::schema::SchemaValidator;
+use rusty_config::value::{self, Value};
+use rusty_config::{convert, edit, ConfigError, ConfigResult};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "rusty-config",
+    version,
+    about = "Inspect, convert and edit config files"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that a file parses and satisfies a JSON Schema
+    Validate {
+        file: PathBuf,
+        /// JSON Schema the document must satisfy
+        #[arg(long, value_name = "PATH")]
+        schema: Option<PathBuf>,
+    },
+    /// Convert a file to the format of the output file
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Output format, instead of the output file extension
+        #[arg(long, value_name = "FORMAT")]
+        to: Option<ConfigFormat>,
+        /// Write nothing if any value does not survive the conversion unchanged
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Print the value at a dotted key path
+    Get { file: PathBuf, key: String },
+    /// Set values at dotted key paths, keeping comments where possible
+    Set {
+        file: PathBuf,
+        /// Values are parsed as JSON where possible, e.g. `port=8080`
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        assignments: Vec<String>,
+    },
+    /// Compare two files by content, whatever their formats
+    ///
+    /// INI, properties and dotenv files hold only strings, so when either
+    /// file is in one of these formats, values are compared as text.
+    Diff { old: PathBuf, new: PathBuf },
+    /// Rewrite files in the canonical layout of their format
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Only report files that are not formatted
+        #[arg(long)]
+        check: bool,
+        /// Format files with comments too, dropping the comments
+        #[arg(long)]
+        force: bool,
+    },
+    /// Deep-merge overlays onto a base file, later files win
+    Merge {
+        base: PathBuf,
+        #[arg(required = true)]
+        overlays: Vec<PathBuf>,
+        /// Write to a file instead of standard output
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Output format, defaults to the output file's or the base file's
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<ConfigFormat>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse().command).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn run(command: Command) -> ConfigResult<ExitCode> {
+    match command {
+        Command::Validate { file, schema } => {
+            let (_, document) = read(&file).await?;
+            if let Some(schema) = schema {
+                let result = SchemaValidator::from_file(&schema)
+                    .await?
+                    .validate(&document);
+                if !result.is_valid {
+                    for error in &result.errors {
+                        eprintln!("{}: {error}", file.display());
+                    }
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+            println!("{}: ok", file.display());
+        }
+        Command::Convert {
+            input,
+            output,
+            to,
+            strict,
+        } => {
+            let content = tokio::fs::read(&input)
+                .await
+                .map_err(|e| ConfigError::InvalidPath(format!("{}: {e}", input.display())))?;
+            let from = format_of(&input, ConfigFormat::Unknown);
+            let format = to.unwrap_or_else(|| format_of(&output, ConfigFormat::Json));
+            let conversion = match convert::convert(&content, &from, &format) {
+                Err(ConfigError::Conversion(issues)) => {
+                    for issue in issues {
+                        eprintln!("{}: {issue}", input.display());
+                    }
+                    return Ok(ExitCode::FAILURE);
+                }
+                result => result?,
+            };
+            for issue in &conversion.report.issues {
+                eprintln!("warning: {}: {issue}", input.display());
+            }
+            if strict && !conversion.report.is_lossless() {
+                return Ok(ExitCode::FAILURE);
+            }
+            convert::write_output(&output, &conversion.content).await?;
+        }
+        Command::Get { file, key } => {
+            let (_, document) = read(&file).await?;
+            match value::get_path(&document, &key) {
+                Some(Value::String(text)) => println!("{text}"),
+                Some(found @ (Value::Object(_) | Value::Array(_))) => {
+                    println!("{}", serde_json::to_string_pretty(found)?)
+                }
+                Some(found) => println!("{found}"),
+                None => {
+                    eprintln!("{}: no value at '{key}'", file.display());
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        Command::Set { file, assignments } => {
+            let assignments = assignments
+                .iter()
+                .map(|assignment| parse_override(assignment))
+                .collect::<ConfigResult<Vec<_>>>()?;
+            let mut rewritten = false;
+            for (key, new_value) in assignments {
+                rewritten |= edit::set_in_file(&file, &key, new_value).await?.rewritten;
+            }
+            if rewritten {
+                eprintln!(
+                    "note: {} was rewritten, comments and formatting were not kept",
+                    file.display()
+                );
+            }
+        }
+        Command::Diff { old, new } => {
+            let (old_format, mut old) = read(&old).await?;
+            let (new_format, mut new) = read(&new).await?;
+            if old_format.is_flat() || new_format.is_flat() {
+                stringify_leaves(&mut old);
+                stringify_leaves(&mut new);
+            }
+            let changes = value::diff(&old, &new);
+            for change in &changes {
+                println!("{change}");
+            }
+            if !changes.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Fmt {
+            files,
+            check,
+            force,
+        } => {
+            let mut unformatted = false;
+            for file in files {
+                #[cfg(feature = "sops")]
+                if rusty_config::sops::is_encrypted_file(&file).await {
+                    eprintln!("{}: is SOPS-encrypted, edit it with sops", file.display());
+                    unformatted = true;
+                    continue;
+                }
+                let original = tokio::fs::read(&file).await?;
+                let (format, document) = read(&file).await?;
+                let text = String::from_utf8_lossy(&original);
+                if !force && convert::has_comments(&text, &format) {
+                    eprintln!(
+                        "{}: has comments that formatting would drop, pass --force to format anyway",
+                        file.display()
+                    );
+                    unformatted = true;
+                    continue;
+                }
+                let formatted = loader::save_to_bytes(&document, &format)?;
+                if formatted == original {
+                    continue;
+                }
+                if check {
+                    println!("{}", file.display());
+                    unformatted = true;
+                } else {
+                    convert::write_output(&file, &formatted).await?;
+                }
+            }
+            if unformatted {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Merge {
+            base,
+            overlays,
+            output,
+            format,
+        } => {
+            let (base_format, mut merged) = read(&base).await?;
+            for overlay in overlays {
+                value::deep_merge(&mut merged, read(&overlay).await?.1);
+            }
+            let format = format.unwrap_or_else(|| match &output {
+                Some(output) => format_of(output, base_format),
+                None => base_format,
+            });
+            let content = loader::save_to_bytes(&merged, &format)?;
+            match output {
+                Some(output) => convert::write_output(&output, &content).await?,
+                None => std::io::stdout().write_all(&content)?,
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Format from the file extension, `fallback` if it has none
+fn format_of(path: &Path, fallback: ConfigFormat) -> ConfigFormat {
+    match loader::detect_format(path) {
+        Some(ConfigFormat::Unknown) | None => fallback,
+        Some(format) => format,
+    }
+}
+
+/// Replace every scalar leaf with its text, as flat formats store it
+fn stringify_leaves(tree: &mut Value) {
+    match tree {
+        Value::Object(table) => table.values_mut().for_each(stringify_leaves),
+        Value::Array(items) => items.iter_mut().for_each(stringify_leaves),
+        leaf => *leaf = Value::String(value::scalar_to_string(leaf)),
+    }
+}
+
+/// Read a file into a value tree together with its format
+async fn read(path: &Path) -> ConfigResult<(ConfigFormat, Value)> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| ConfigError::InvalidPath(format!("{}: {e}", path.display())))?;
+    match loader::detect_format(path) {
+        Some(ConfigFormat::Unknown) | None => loader::detect_content(&bytes),
+        Some(format) => {
+            let document = loader::load_from_bytes(bytes, &format)
+                .map_err(|e| ConfigError::InvalidFormat(format!("{}: {e}", path.display())))?;
+            Ok((format, document))
+        }
+    }
+}
@@ -1,6 +1,13 @@
+use crate::cli::CliArgs;
+use crate::loader::{ConfigFormat, LoadOptions, UnknownKeys};
+use crate::migration::Migrations;
+use crate::resolver::SecretResolver;
+use crate::rules::Rule;
+use crate::value::Value;
 use crate::{Config, ConfigError, ConfigResult};
 use serde::{de::DeserializeOwned, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Builder for creating configurations
 pub struct ConfigBuilder {
@@ -9,6 +16,22 @@ pub struct ConfigBuilder {
     validate_on_load: bool,
     create_if_missing: bool,
     default_content: Option<String>,
+    overrides: Vec<(String, Value)>,
+    #[cfg(feature = "encryption")]
+    keyring: Option<Arc<crate::encryption::Keyring>>,
+    #[cfg(feature = "sops")]
+    sops_keys: Option<Arc<crate::sops::SopsKeys>>,
+    resolve_secrets: bool,
+    secret_resolvers: Vec<Arc<dyn SecretResolver>>,
+    schema_url: Option<String>,
+    #[cfg(feature = "schema-validation")]
+    schema: Option<Arc<crate::schema::SchemaValidator>>,
+    rules: Vec<Rule>,
+    migrations: Option<Migrations>,
+    rewrite_migrated_file: bool,
+    deprecated_keys: Vec<(String, String)>,
+    rewrite_deprecated_keys: bool,
+    unknown_keys: UnknownKeys,
 }
 
 impl ConfigBuilder {
@@ -20,6 +43,22 @@ impl ConfigBuilder {
             validate_on_load: false,
             create_if_missing: false,
             default_content: None,
+            overrides: Vec::new(),
+            #[cfg(feature = "encryption")]
+            keyring: None,
+            #[cfg(feature = "sops")]
+            sops_keys: None,
+            resolve_secrets: false,
+            secret_resolvers: Vec::new(),
+            schema_url: None,
+            #[cfg(feature = "schema-validation")]
+            schema: None,
+            rules: Vec::new(),
+            migrations: None,
+            rewrite_migrated_file: false,
+            deprecated_keys: Vec::new(),
+            rewrite_deprecated_keys: false,
+            unknown_keys: UnknownKeys::Ignore,
         }
     }
 
@@ -53,35 +92,246 @@ impl ConfigBuilder {
         self
     }
 
+    /// Override a single value at a dotted key path
+    ///
+    /// Overrides take precedence over the file and the typed defaults and
+    /// are applied again on every reload.
+    pub fn set<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Apply `--config` and `--set` command-line arguments
+    ///
+    /// `--config` replaces the file path, `--set` values are layered on top
+    /// of everything else. See [`crate::cli`].
+    pub fn cli_args(mut self, args: CliArgs) -> Self {
+        if let Some(config) = args.config {
+            self.file_path = Some(config);
+        }
+        self.overrides.extend(args.overrides);
+        self
+    }
+
+    /// Decrypt `ENC[...]` values at load and re-encrypt them on save
+    #[cfg(feature = "encryption")]
+    pub fn encryption(mut self, keyring: crate::encryption::Keyring) -> Self {
+        self.keyring = Some(Arc::new(keyring));
+        self
+    }
+
+    /// Decrypt SOPS-encrypted files with the given keys
+    ///
+    /// Without this, keys are looked up like the `sops` command does, see
+    /// [`SopsKeys::from_env`](crate::sops::SopsKeys::from_env).
+    #[cfg(feature = "sops")]
+    pub fn sops_keys(mut self, keys: crate::sops::SopsKeys) -> Self {
+        self.sops_keys = Some(Arc::new(keys));
+        self
+    }
+
+    /// Resolve `file://` and `env://` secret references at load
+    ///
+    /// See [`crate::resolver`]. With hot-reload, changes to referenced
+    /// secret files reload the config as well.
+    pub fn resolve_secrets(mut self, enabled: bool) -> Self {
+        self.resolve_secrets = enabled;
+        self
+    }
+
+    /// Resolve secret references of a custom scheme at load
+    pub fn secret_resolver<R: SecretResolver + 'static>(mut self, resolver: R) -> Self {
+        self.secret_resolvers.push(Arc::new(resolver));
+        self
+    }
+
+    /// Link files created by [`create_if_missing`](Self::create_if_missing) to a JSON Schema
+    ///
+    /// YAML and TOML files get a modeline, JSON files a `"$schema"` property,
+    /// see [`crate::schema::add_schema_reference`].
+    pub fn schema_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.schema_url = Some(url.into());
+        self
+    }
+
+    /// Reject documents that do not satisfy a JSON Schema
+    ///
+    /// Checked on load and on every reload, before deserializing. A failing
+    /// document yields [`ConfigError::ValidationErrors`] with every violation.
+    #[cfg(feature = "schema-validation")]
+    pub fn validate_schema(mut self, schema: crate::schema::SchemaValidator) -> Self {
+        self.schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Add a cross-field rule, see [`crate::rules`]
+    ///
+    /// Checked on load and on every reload, before deserializing. A failing
+    /// document yields [`ConfigError::ValidationErrors`] with every violation
+    /// of every rule.
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Migrate files with an older `schema_version` on every load
+    ///
+    /// Migrations run on the value tree before deprecated keys, defaults and
+    /// validation. Saving the config writes the latest version into files
+    /// that have no version key.
+    pub fn migrations(mut self, migrations: Migrations) -> Self {
+        self.migrations = Some(migrations);
+        self
+    }
+
+    /// Write migrated files back, keeping the original as `<name>.v<version>.bak`
+    ///
+    /// The file is rewritten from its parsed content, so comments are lost.
+    pub fn rewrite_migrated_file(mut self, enabled: bool) -> Self {
+        self.rewrite_migrated_file = enabled;
+        self
+    }
+
+    /// Read a deprecated key path as its replacement
+    ///
+    /// Each load that finds `old` moves its value to `new` and reports a
+    /// warning through [`Config::warnings`], [`Config::watch_events`] and,
+    /// with the `logging` feature, `log::warn!`.
+    pub fn deprecated_key(mut self, old: impl Into<String>, new: impl Into<String>) -> Self {
+        self.deprecated_keys.push((old.into(), new.into()));
+        self
+    }
+
+    /// Write the file back with deprecated keys renamed
+    ///
+    /// The file is rewritten from its parsed content, so comments are lost.
+    pub fn rewrite_deprecated_keys(mut self, enabled: bool) -> Self {
+        self.rewrite_deprecated_keys = enabled;
+        self
+    }
+
+    /// Strict mode: report keys that no field of the config type reads
+    ///
+    /// A typo like `pool_sise` is reported with its path and the closest
+    /// field name, either as a warning or as a load error. Stray keys in a
+    /// struct with a `#[serde(flatten)]` field are not reported, see
+    /// [`from_value_checked`](crate::value::from_value_checked).
+    pub fn unknown_keys(mut self, mode: UnknownKeys) -> Self {
+        self.unknown_keys = mode;
+        self
+    }
+
     /// Build the configuration
     pub async fn build<T>(self) -> ConfigResult<Config<T>>
     where
         T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
     {
-        let file_path = self
-            .file_path
-            .clone()
-            .ok_or_else(|| ConfigError::InvalidPath("No file path specified".to_string()))?;
+        let file_path = self.require_file_path()?;
 
         // Create file if desired and not present
         if self.create_if_missing && !file_path.exists() {
             if let Some(default_content) = self.default_content.clone() {
-                tokio::fs::write(&file_path, default_content).await?;
+                self.create_file(&file_path, default_content.into_bytes())
+                    .await?;
             } else {
                 // Create empty default config
                 let default_config = serde_json::to_string_pretty(&serde_json::Value::Object(
                     serde_json::Map::new(),
                 ))?;
-                tokio::fs::write(&file_path, default_config).await?;
+                self.create_file(&file_path, default_config.into_bytes())
+                    .await?;
             }
         }
 
-        // Create config
-        let config = if self.hot_reload {
-            Config::from_file_with_watcher(&file_path).await?
-        } else {
-            Config::from_file(&file_path).await?
+        let options = self.load_options(None);
+        self.finish(file_path, options).await
+    }
+
+    /// Build the configuration on top of `T::default()`
+    ///
+    /// The file only needs to contain the keys that differ from the defaults:
+    /// `T::default()` is serialized into a value tree and the file is
+    /// deep-merged over it before deserializing, on load and on every reload.
+    /// With [`create_if_missing`](Self::create_if_missing) the typed default
+    /// is written in the file's format, [`default_content`](Self::default_content)
+    /// is ignored.
+    pub async fn build_with_defaults<T>(self) -> ConfigResult<Config<T>>
+    where
+        T: Default + Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
+    {
+        let file_path = self.require_file_path()?;
+        let defaults = T::default();
+
+        if self.create_if_missing && !file_path.exists() {
+            let content = crate::loader::save_to_bytes(&defaults, &new_file_format(&file_path))?;
+            self.create_file(&file_path, content).await?;
+        }
+
+        let defaults = crate::secret::expose_secrets(|| serde_json::to_value(&defaults))?;
+        let options = self.load_options(Some(defaults));
+        self.finish(file_path, options).await
+    }
+
+    fn load_options(&self, defaults: Option<Value>) -> LoadOptions {
+        LoadOptions {
+            defaults,
+            overrides: self.overrides.clone(),
+            #[cfg(feature = "encryption")]
+            keyring: self.keyring.clone(),
+            #[cfg(feature = "sops")]
+            sops_keys: self.sops_keys.clone(),
+            #[cfg(feature = "schema-validation")]
+            schema: self.schema.clone(),
+            rules: self.rules.clone(),
+            migrations: self.migrations.clone(),
+            rewrite_migrated_file: self.rewrite_migrated_file,
+            deprecated_keys: self.deprecated_keys.clone(),
+            rewrite_deprecated_keys: self.rewrite_deprecated_keys,
+            unknown_keys: self.unknown_keys,
+            resolvers: self.resolvers(),
+        }
+    }
+
+    /// Write a new config file, linked to the schema if one is set
+    async fn create_file(&self, path: &Path, content: Vec<u8>) -> ConfigResult<()> {
+        let format = new_file_format(path);
+        let content = match &self.schema_url {
+            Some(url) if !format.is_binary() => {
+                let text = String::from_utf8(content)
+                    .map_err(|err| ConfigError::InvalidFormat(err.to_string()))?;
+                crate::schema::add_schema_reference(&text, &format, url)?.into_bytes()
+            }
+            _ => content,
         };
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+
+    /// Custom resolvers first, so they can replace a built-in scheme
+    fn resolvers(&self) -> Vec<Arc<dyn SecretResolver>> {
+        let mut resolvers = self.secret_resolvers.clone();
+        if self.resolve_secrets {
+            resolvers.extend(crate::resolver::builtin_resolvers());
+        }
+        resolvers
+    }
+
+    fn require_file_path(&self) -> ConfigResult<PathBuf> {
+        self.file_path
+            .clone()
+            .ok_or_else(|| ConfigError::InvalidPath("No file path specified".to_string()))
+    }
+
+    /// Load the config and start the watcher if requested
+    async fn finish<T>(self, file_path: PathBuf, options: LoadOptions) -> ConfigResult<Config<T>>
+    where
+        T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
+    {
+        // Create config
+        let mut config = Config::from_file_with_options(&file_path, options).await?;
+        if self.hot_reload {
+            config.start_watcher().await?;
+        }
 
         // Validate if desired
         if self.validate_on_load {
@@ -102,6 +352,14 @@ impl ConfigBuilder {
     }
 }
 
+/// Format of a file that does not exist yet, JSON if the extension is unknown
+fn new_file_format(path: &Path) -> ConfigFormat {
+    match crate::loader::detect_format(path) {
+        Some(ConfigFormat::Unknown) | None => ConfigFormat::Json,
+        Some(format) => format,
+    }
+}
+
 impl Default for ConfigBuilder {
     fn default() -> Self {
         Self::new()
@@ -180,3 +438,493 @@ impl ConfigBuilder {
             .hot_reload(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct ServerConfig {
+        host: String,
+        port: u16,
+        workers: u32,
+    }
+
+    impl Default for ServerConfig {
+        fn default() -> Self {
+            Self {
+                host: "localhost".to_string(),
+                port: 8080,
+                workers: 4,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_with_defaults_merges_partial_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.toml");
+        tokio::fs::write(&path, "port = 9090\n").await.unwrap();
+
+        let mut config = ConfigBuilder::new()
+            .file(&path)
+            .build_with_defaults::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().port, 9090);
+        assert_eq!(config.get().host, "localhost");
+
+        // Defaults also apply on reload
+        tokio::fs::write(&path, "workers = 8\n").await.unwrap();
+        config.reload().await.unwrap();
+        assert_eq!(
+            config.get(),
+            ServerConfig {
+                workers: 8,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cli_args_take_precedence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.json");
+        tokio::fs::write(&path, r#"{"host": "file", "port": 1, "workers": 2}"#)
+            .await
+            .unwrap();
+
+        let args = CliArgs::parse([
+            "app".to_string(),
+            format!("--config={}", path.display()),
+            "--set".to_string(),
+            "port=9090".to_string(),
+        ])
+        .unwrap();
+
+        let config = ConfigBuilder::new()
+            .file("ignored.json")
+            .cli_args(args)
+            .set("workers", 16)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(
+            config.get(),
+            ServerConfig {
+                host: "file".to_string(),
+                port: 9090,
+                workers: 16,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_with_defaults_creates_typed_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .create_if_missing(true)
+            .default_content("ignored".to_string())
+            .build_with_defaults::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get(), ServerConfig::default());
+
+        let written: ServerConfig = crate::loader::load_from_file(&path).await.unwrap();
+        assert_eq!(written, ServerConfig::default());
+    }
+
+    /// Resolves `static://<value>` to the value itself
+    #[derive(Debug)]
+    struct StaticResolver;
+
+    #[async_trait::async_trait]
+    impl SecretResolver for StaticResolver {
+        fn scheme(&self) -> &str {
+            "static"
+        }
+
+        async fn resolve(&self, reference: &str) -> ConfigResult<String> {
+            Ok(reference.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secret_references_resolved_and_kept_on_save() {
+        std::env::set_var("RUSTY_CONFIG_TEST_HOST", "db.internal");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        tokio::fs::write(
+            &path,
+            "host: env://RUSTY_CONFIG_TEST_HOST\nport: static://5432\nworkers: 2\n",
+        )
+        .await
+        .unwrap();
+
+        let mut config = ConfigBuilder::new()
+            .file(&path)
+            .resolve_secrets(true)
+            .secret_resolver(StaticResolver)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().host, "db.internal");
+        assert_eq!(config.get().port, 5432);
+
+        config.get_mut().data.workers = 8;
+        config.get_mut().data.port = 6543;
+        config.save().await.unwrap();
+
+        let saved = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(
+            saved.contains("host: env://RUSTY_CONFIG_TEST_HOST"),
+            "{saved}"
+        );
+        assert!(saved.contains("port: 6543"), "{saved}");
+        assert!(saved.contains("workers: 8"), "{saved}");
+    }
+
+    #[tokio::test]
+    async fn test_created_files_link_schema() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = "https://example.com/server.schema.json";
+
+        let yaml = temp_dir.path().join("server.yaml");
+        let config = ConfigBuilder::new()
+            .file(&yaml)
+            .create_if_missing(true)
+            .schema_url(url)
+            .build_with_defaults::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get(), ServerConfig::default());
+        let content = tokio::fs::read_to_string(&yaml).await.unwrap();
+        assert!(content.starts_with(&format!("# yaml-language-server: $schema={url}\n")));
+
+        let json = temp_dir.path().join("server.json");
+        ConfigBuilder::new()
+            .file(&json)
+            .create_if_missing(true)
+            .schema_url(url)
+            .build_with_defaults::<ServerConfig>()
+            .await
+            .unwrap();
+        let written: Value = crate::loader::load_from_file(&json).await.unwrap();
+        assert_eq!(written["$schema"], url);
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_keys_warn_and_rewrite() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        tokio::fs::write(
+            &path,
+            "hostname: file\nport: 8080\nthreads: 2\nworkers: 4\n",
+        )
+        .await
+        .unwrap();
+
+        let builder = || {
+            ConfigBuilder::new()
+                .file(&path)
+                .deprecated_key("hostname", "host")
+                .deprecated_key("threads", "workers")
+        };
+        let config = builder().build::<ServerConfig>().await.unwrap();
+        assert_eq!(config.get().host, "file");
+        assert_eq!(config.get().workers, 4);
+        let warnings: Vec<_> = config
+            .warnings()
+            .into_iter()
+            .map(|warning| (warning.field, warning.message))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    "hostname".to_string(),
+                    "'hostname' is deprecated, use 'host' instead".to_string()
+                ),
+                (
+                    "threads".to_string(),
+                    "'threads' is deprecated and ignored because 'workers' is set".to_string()
+                ),
+            ]
+        );
+
+        // Reloads report warnings on the event stream
+        let mut config = config;
+        let mut events = config.watch_events();
+        config.reload().await.unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            crate::watcher::ConfigEvent::Warning(warning) if warning.field == "hostname"
+        ));
+
+        let config = builder()
+            .rewrite_deprecated_keys(true)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.warnings().len(), 2);
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content, "port: 8080\nworkers: 4\nhost: file\n");
+        let config = builder().build::<ServerConfig>().await.unwrap();
+        assert!(config.warnings().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_rewrite_keeps_file_without_deprecated_keys() {
+        use crate::migration::Migration;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        let original = "# deployed by ops
+host: file
+port: 8080
+threads: 4
+";
+        tokio::fs::write(&path, original).await.unwrap();
+        // The backup cannot be written over a directory
+        std::fs::create_dir(temp_dir.path().join("server.yaml.v1.bak")).unwrap();
+
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .migrations(
+                Migrations::new().migration(Migration::new(1, 2).rename("threads", "workers")),
+            )
+            .rewrite_migrated_file(true)
+            .deprecated_key("hostname", "host")
+            .rewrite_deprecated_keys(true)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().workers, 4);
+        assert_eq!(config.warnings().len(), 1);
+        assert_eq!(
+            config.warnings()[0].code,
+            crate::validator::codes::MIGRATION
+        );
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), original);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_rewrite_file_and_stamp_saves() {
+        use crate::migration::Migration;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        let original = "server:\n  addr: old\n  port: 8080\nthreads: 4\n";
+        tokio::fs::write(&path, original).await.unwrap();
+
+        let builder = || {
+            ConfigBuilder::new().file(&path).migrations(
+                Migrations::new()
+                    .migration(Migration::new(1, 2).rename("threads", "workers"))
+                    .migration(
+                        Migration::new(2, 3)
+                            .rename("server.addr", "host")
+                            .move_subtree("server.host", "host")
+                            .move_subtree("server.port", "port")
+                            .custom(|tree| {
+                                crate::value::remove_path(tree, "server");
+                                Ok(())
+                            }),
+                    ),
+            )
+        };
+        let config = builder().build::<ServerConfig>().await.unwrap();
+        assert_eq!(config.get().host, "old");
+        assert_eq!(config.get().workers, 4);
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), original);
+
+        // Saved files get the latest version, so they are not migrated again
+        config.save().await.unwrap();
+        let saved: Value = crate::loader::load_from_file(&path).await.unwrap();
+        assert_eq!(saved["schema_version"], 3);
+
+        tokio::fs::write(&path, original).await.unwrap();
+        builder()
+            .rewrite_migrated_file(true)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(
+            content,
+            "workers: 4\nhost: old\nport: 8080\nschema_version: 3\n"
+        );
+        let backup = temp_dir.path().join("server.yaml.v1.bak");
+        assert_eq!(tokio::fs::read_to_string(backup).await.unwrap(), original);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.json");
+        tokio::fs::write(
+            &path,
+            r#"{"$schema": "server.schema.json", "host": "file", "port": 8080, "wrokers": 2, "workers": 2, "tls": true}"#,
+        )
+        .await
+        .unwrap();
+
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .unknown_keys(UnknownKeys::Warn)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        let messages: Vec<_> = config
+            .warnings()
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "wrokers: unknown key 'wrokers', did you mean 'workers'? [unknown_key]",
+                "tls: unknown key 'tls' [unknown_key]",
+            ]
+        );
+
+        let result = ConfigBuilder::new()
+            .file(&path)
+            .unknown_keys(UnknownKeys::Deny)
+            .build::<ServerConfig>()
+            .await;
+        assert!(matches!(result, Err(ConfigError::ValidationErrors(errors)) if errors.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn test_migrations_with_denied_unknown_keys() {
+        use crate::migration::Migration;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        tokio::fs::write(&path, "host: file\nport: 8080\nthreads: 4\n")
+            .await
+            .unwrap();
+
+        let build = |path: &Path| {
+            ConfigBuilder::new()
+                .file(path)
+                .migrations(
+                    Migrations::new().migration(Migration::new(1, 2).rename("threads", "workers")),
+                )
+                .unknown_keys(UnknownKeys::Deny)
+                .build::<ServerConfig>()
+        };
+        let config = build(&path).await.unwrap();
+        assert_eq!(config.get().workers, 4);
+        assert!(config.warnings().is_empty());
+
+        // Files already at the latest version carry the key as well
+        config.save().await.unwrap();
+        build(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_typed_formats_stay_strict_with_options() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.json");
+        tokio::fs::write(&path, r#"{"host": 123, "port": "8080", "workers": 2}"#)
+            .await
+            .unwrap();
+
+        assert!(ConfigBuilder::new()
+            .file(&path)
+            .build::<ServerConfig>()
+            .await
+            .is_err());
+        assert!(ConfigBuilder::new()
+            .file(&path)
+            .unknown_keys(UnknownKeys::Warn)
+            .build::<ServerConfig>()
+            .await
+            .is_err());
+
+        // Overrides arrive as text and are still read as the field's type
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .set("host", 8080)
+            .set("port", "9090")
+            .unknown_keys(UnknownKeys::Warn)
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(
+            (config.get().host.as_str(), config.get().port),
+            ("8080", 9090)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rules_gate_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        tokio::fs::write(&path, "host: file\nport: 8080\nworkers: 2\n")
+            .await
+            .unwrap();
+
+        let result = ConfigBuilder::new()
+            .file(&path)
+            .rule(crate::rules::less_or_equal("port", "workers"))
+            .rule(crate::rules::requires("host", ["tls"]))
+            .build::<ServerConfig>()
+            .await;
+        match result {
+            Err(ConfigError::ValidationErrors(errors)) => {
+                let fields: Vec<_> = errors.iter().map(|error| error.field.as_str()).collect();
+                assert_eq!(fields, ["port", "tls"]);
+            }
+            other => panic!("expected rule violations, got {other:?}"),
+        }
+
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .rule(crate::rules::less_or_equal("workers", "port"))
+            .build::<ServerConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().workers, 2);
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_schema_gate_rejects_document() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let schema_path = temp_dir.path().join("server.schema.yaml");
+        tokio::fs::write(
+            &schema_path,
+            "type: object\nproperties:\n  port:\n    maximum: 1024\n",
+        )
+        .await
+        .unwrap();
+        let path = temp_dir.path().join("server.yaml");
+        tokio::fs::write(&path, "host: file\nport: 8080\nworkers: 2\n")
+            .await
+            .unwrap();
+
+        let schema = crate::schema::SchemaValidator::from_file(&schema_path)
+            .await
+            .unwrap();
+        let result = ConfigBuilder::new()
+            .file(&path)
+            .validate_schema(schema)
+            .build::<ServerConfig>()
+            .await;
+        match result {
+            Err(ConfigError::ValidationErrors(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].field, "/port");
+                assert_eq!(errors[0].code, "maximum");
+            }
+            other => panic!("expected schema violation, got {other:?}"),
+        }
+    }
+}
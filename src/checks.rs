@@ -0,0 +1,488 @@
+//! Asynchronous checks of local resources
+//!
+//! [`AsyncChecks`] runs checks like "the TLS certificate parses and is not
+//! about to expire" or "the listen port is free" concurrently, each with its
+//! own timeout. [`ResourceValidator`] has the common ones:
+//!
+//! ```rust,no_run
+//! use rusty_config::checks::{AsyncChecks, ResourceValidator};
+//! use std::time::Duration;
+//!
+//! # async fn example() {
+//! let result = AsyncChecks::new()
+//!     .check("listen", ResourceValidator::port_bindable("0.0.0.0:8080", "listen"))
+//!     .check_with_timeout(
+//!         "state_file",
+//!         Duration::from_secs(1),
+//!         ResourceValidator::readable_file("/var/lib/app/state", "state_file"),
+//!     )
+//!     .run()
+//!     .await;
+//! for error in &result.errors {
+//!     eprintln!("{error}");
+//! }
+//! # }
+//! ```
+//!
+//! Configs return their checks from
+//! [`DetailedValidatable::async_checks`](crate::validator::DetailedValidatable::async_checks),
+//! which [`validate_detailed`](crate::validator::validate_detailed) runs.
+
+use crate::units::ConfigDuration;
+use crate::validator::{codes, invalid, ValidationContext, ValidationError, ValidationResult};
+use crate::ConfigResult;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+/// A check of one field, with an optional timeout of its own
+struct PendingCheck<'a> {
+    field: String,
+    timeout: Option<Duration>,
+    check: BoxFuture<'a, ConfigResult<()>>,
+}
+
+/// A set of asynchronous checks, run concurrently
+pub struct AsyncChecks<'a> {
+    checks: Vec<PendingCheck<'a>>,
+    timeout: Duration,
+    concurrency: usize,
+}
+
+impl<'a> AsyncChecks<'a> {
+    /// Create an empty set with a 5s timeout and up to 8 checks at a time
+    pub fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            timeout: Duration::from_secs(5),
+            concurrency: 8,
+        }
+    }
+
+    /// Timeout for checks added without one
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many checks run at the same time
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Add a check of `field`
+    pub fn check<F>(self, field: impl Into<String>, check: F) -> Self
+    where
+        F: Future<Output = ConfigResult<()>> + Send + 'a,
+    {
+        self.push(field.into(), None, check.boxed())
+    }
+
+    /// Add a check of `field` with its own timeout
+    pub fn check_with_timeout<F>(
+        self,
+        field: impl Into<String>,
+        timeout: Duration,
+        check: F,
+    ) -> Self
+    where
+        F: Future<Output = ConfigResult<()>> + Send + 'a,
+    {
+        self.push(field.into(), Some(timeout), check.boxed())
+    }
+
+    fn push(
+        mut self,
+        field: String,
+        timeout: Option<Duration>,
+        check: BoxFuture<'a, ConfigResult<()>>,
+    ) -> Self {
+        self.checks.push(PendingCheck {
+            field,
+            timeout,
+            check,
+        });
+        self
+    }
+
+    /// Number of checks in the set
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Run all checks and collect their failures in the order they were added
+    ///
+    /// A check that exceeds its timeout fails with the code `timeout`.
+    pub async fn run(self) -> ValidationResult {
+        let default_timeout = self.timeout;
+        let outcomes: Vec<_> = futures::stream::iter(self.checks)
+            .map(|pending| async move {
+                let timeout = pending.timeout.unwrap_or(default_timeout);
+                let outcome = tokio::time::timeout(timeout, pending.check).await;
+                (pending.field, timeout, outcome)
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut ctx = ValidationContext::new();
+        for (field, timeout, outcome) in outcomes {
+            match outcome {
+                Ok(result) => {
+                    ctx.check(&field, result);
+                }
+                Err(_) => {
+                    ctx.add_error(ValidationError::templated(
+                        field,
+                        codes::TIMEOUT,
+                        &[("timeout", ConfigDuration::from(timeout).to_string())],
+                    ));
+                }
+            }
+        }
+        ctx.into_result()
+    }
+}
+
+impl Default for AsyncChecks<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asynchronous validators for local resources
+///
+/// Like [`TypeValidator`](crate::validator::TypeValidator), every check
+/// fails with one coded [`ValidationError`] for `field_name`.
+pub struct ResourceValidator;
+
+impl ResourceValidator {
+    /// Validate that a file exists and can be opened for reading
+    pub async fn readable_file(path: impl AsRef<Path>, field_name: &str) -> ConfigResult<()> {
+        let path = path.as_ref();
+        let metadata = tokio::fs::metadata(path).await.map_err(|e| {
+            invalid(
+                field_name,
+                codes::PATH_NOT_FOUND,
+                &[
+                    ("path", path.display().to_string()),
+                    ("reason", e.to_string()),
+                ],
+            )
+        })?;
+        if !metadata.is_file() {
+            return Err(invalid(
+                field_name,
+                codes::NOT_A_FILE,
+                &[("path", path.display().to_string())],
+            ));
+        }
+        tokio::fs::File::open(path).await.map_err(|_| {
+            invalid(
+                field_name,
+                codes::PERMISSION_DENIED,
+                &[
+                    ("kind", "read".to_string()),
+                    ("path", path.display().to_string()),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Validate that a Unix domain socket exists at `path`
+    #[cfg(unix)]
+    pub async fn unix_socket(path: impl AsRef<Path>, field_name: &str) -> ConfigResult<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = path.as_ref();
+        let metadata = tokio::fs::metadata(path).await.map_err(|e| {
+            invalid(
+                field_name,
+                codes::PATH_NOT_FOUND,
+                &[
+                    ("path", path.display().to_string()),
+                    ("reason", e.to_string()),
+                ],
+            )
+        })?;
+        if !metadata.file_type().is_socket() {
+            return Err(invalid(
+                field_name,
+                codes::NOT_A_SOCKET,
+                &[("path", path.display().to_string())],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate that a TCP address like `0.0.0.0:8080` can be bound
+    ///
+    /// The listener is closed right away, so the check can race with other
+    /// processes taking the port.
+    pub async fn port_bindable(address: &str, field_name: &str) -> ConfigResult<()> {
+        tokio::net::TcpListener::bind(address)
+            .await
+            .map(drop)
+            .map_err(|e| {
+                invalid(
+                    field_name,
+                    codes::PORT_UNAVAILABLE,
+                    &[("address", address.to_string()), ("reason", e.to_string())],
+                )
+            })
+    }
+
+    /// Validate that a PEM file holds an X.509 certificate valid for at
+    /// least `min_validity` from now
+    #[cfg(feature = "x509")]
+    pub async fn tls_certificate(
+        path: impl AsRef<Path>,
+        min_validity: Duration,
+        field_name: &str,
+    ) -> ConfigResult<()> {
+        let path = path.as_ref();
+        let content = tokio::fs::read(path).await.map_err(|e| {
+            invalid(
+                field_name,
+                codes::PATH_NOT_FOUND,
+                &[
+                    ("path", path.display().to_string()),
+                    ("reason", e.to_string()),
+                ],
+            )
+        })?;
+        let not_a_certificate = |reason: String| {
+            invalid(
+                field_name,
+                codes::INVALID_CERTIFICATE,
+                &[
+                    ("kind", "parse".to_string()),
+                    ("path", path.display().to_string()),
+                    ("reason", reason),
+                ],
+            )
+        };
+        let (_, pem) = x509_parser::pem::parse_x509_pem(&content)
+            .map_err(|e| not_a_certificate(e.to_string()))?;
+        let certificate = pem
+            .parse_x509()
+            .map_err(|e| not_a_certificate(e.to_string()))?;
+
+        let validity = certificate.validity();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        if validity.not_before.timestamp() > now {
+            return Err(invalid(
+                field_name,
+                codes::INVALID_CERTIFICATE,
+                &[
+                    ("kind", "not_yet_valid".to_string()),
+                    ("not_before", validity.not_before.to_string()),
+                ],
+            ));
+        }
+        let remaining = validity.not_after.timestamp() - now;
+        if remaining < min_validity.as_secs() as i64 {
+            let kind = if remaining < 0 { "expired" } else { "expiring" };
+            return Err(invalid(
+                field_name,
+                codes::CERTIFICATE_EXPIRED,
+                &[
+                    ("kind", kind.to_string()),
+                    ("not_after", validity.not_after.to_string()),
+                    (
+                        "min_validity",
+                        ConfigDuration::from(min_validity).to_string(),
+                    ),
+                ],
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes_of(result: &ValidationResult) -> Vec<(&str, &str)> {
+        result
+            .errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.code.as_str()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_checks_run_concurrently_with_timeouts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("state");
+        tokio::fs::write(&file, "ok").await.unwrap();
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let taken_address = taken.local_addr().unwrap().to_string();
+
+        let started = std::time::Instant::now();
+        let result = AsyncChecks::new()
+            .timeout(Duration::from_millis(200))
+            .check("slow", async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .check_with_timeout("slower", Duration::from_millis(300), async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .check("state", ResourceValidator::readable_file(&file, "state"))
+            .check(
+                "missing",
+                ResourceValidator::readable_file(temp_dir.path().join("x"), "missing"),
+            )
+            .check(
+                "dir",
+                ResourceValidator::readable_file(temp_dir.path(), "dir"),
+            )
+            .check(
+                "listen",
+                ResourceValidator::port_bindable("127.0.0.1:0", "listen"),
+            )
+            .check(
+                "taken",
+                ResourceValidator::port_bindable(&taken_address, "taken"),
+            )
+            .run()
+            .await;
+
+        // Both sleeping checks time out at the same time
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(
+            codes_of(&result),
+            [
+                ("slow", codes::TIMEOUT),
+                ("slower", codes::TIMEOUT),
+                ("missing", codes::PATH_NOT_FOUND),
+                ("dir", codes::NOT_A_FILE),
+                ("taken", codes::PORT_UNAVAILABLE),
+            ]
+        );
+        assert_eq!(result.errors[1].message, "check timed out after 300ms");
+    }
+
+    struct Server {
+        listen: String,
+        workers: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::validator::Validatable for Server {
+        async fn validate(&self) -> ConfigResult<()> {
+            crate::validator::validate(self).await
+        }
+
+        async fn validate_into(&self, ctx: &mut ValidationContext) {
+            ctx.check(
+                "workers",
+                crate::validator::TypeValidator::range(self.workers, 1, 64, "workers"),
+            );
+        }
+    }
+
+    impl crate::validator::DetailedValidatable for Server {
+        fn async_checks(&self) -> AsyncChecks<'_> {
+            AsyncChecks::new().check(
+                "listen",
+                ResourceValidator::port_bindable(&self.listen, "listen"),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_detailed_runs_async_checks() {
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Server {
+            listen: taken.local_addr().unwrap().to_string(),
+            workers: 0,
+        };
+        let result = crate::validator::validate_detailed(&server).await.unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(
+            codes_of(&result),
+            [
+                ("workers", codes::RANGE),
+                ("listen", codes::PORT_UNAVAILABLE)
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket = temp_dir.path().join("app.sock");
+        let _listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        assert!(ResourceValidator::unix_socket(&socket, "socket")
+            .await
+            .is_ok());
+        let result = AsyncChecks::new()
+            .check(
+                "socket",
+                ResourceValidator::unix_socket(temp_dir.path(), "socket"),
+            )
+            .run()
+            .await;
+        assert_eq!(codes_of(&result), [("socket", codes::NOT_A_SOCKET)]);
+    }
+
+    #[cfg(feature = "x509")]
+    #[tokio::test]
+    async fn test_tls_certificate() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+        let valid = fixtures.join("valid.pem");
+        let day = Duration::from_secs(86_400);
+
+        let result = AsyncChecks::new()
+            .check(
+                "valid",
+                ResourceValidator::tls_certificate(&valid, day, "valid"),
+            )
+            .check(
+                "expiring",
+                ResourceValidator::tls_certificate(&valid, day * 365 * 200, "expiring"),
+            )
+            .check(
+                "expired",
+                ResourceValidator::tls_certificate(fixtures.join("expired.pem"), day, "expired"),
+            )
+            .check(
+                "garbage",
+                ResourceValidator::tls_certificate(
+                    Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"),
+                    day,
+                    "garbage",
+                ),
+            )
+            .run()
+            .await;
+        assert_eq!(
+            codes_of(&result),
+            [
+                ("expiring", codes::CERTIFICATE_EXPIRED),
+                ("expired", codes::CERTIFICATE_EXPIRED),
+                ("garbage", codes::INVALID_CERTIFICATE),
+            ]
+        );
+        assert!(result.errors[1]
+            .message
+            .starts_with("certificate expired on"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Command-line argument overlay
+//!
+//! Binaries built on RustyConfig share two flags:
+//!
+//! - `--config <path>` selects the config file
+//! - `--set <key.path=value>` overrides a single value, with the highest precedence
+//!
+//! Values are parsed as JSON where possible (`8080`, `true`, `["a", "b"]`,
+//! `{"x": 1}`, `null`), anything else is taken as a plain string.
+//!
+//! ```rust
+//! use rusty_config::cli::CliArgs;
+//!
+//! let args = CliArgs::parse(["app", "--config", "app.yaml", "--set", "server.port=9090"])?;
+//! assert_eq!(args.config.as_deref(), Some(std::path::Path::new("app.yaml")));
+//! assert_eq!(args.overrides[0].0, "server.port");
+//! # Ok::<(), rusty_config::ConfigError>(())
+//! ```
+
+use crate::value::Value;
+use crate::{ConfigError, ConfigResult};
+use std::path::PathBuf;
+
+/// Config-related command-line arguments
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    /// Path given with `--config`
+    pub config: Option<PathBuf>,
+    /// Values given with `--set`, in command-line order
+    pub overrides: Vec<(String, Value)>,
+}
+
+impl CliArgs {
+    /// Parse `--config` and `--set` from an argument list
+    ///
+    /// Both `--flag value` and `--flag=value` are accepted. All other
+    /// arguments are ignored, so binaries can keep their own flags.
+    pub fn parse<I, S>(args: I) -> ConfigResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().map(Into::into);
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            if flag != "--config" && flag != "--set" {
+                continue;
+            }
+
+            let value = match inline {
+                Some(value) => value.to_string(),
+                None => args.next().ok_or_else(|| {
+                    ConfigError::InvalidFormat(format!("Missing value for '{flag}'"))
+                })?,
+            };
+
+            if flag == "--config" {
+                parsed.config = Some(PathBuf::from(value));
+            } else {
+                parsed.overrides.push(parse_override(&value)?);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parse `--config` and `--set` from the arguments of the current process
+    pub fn from_env() -> ConfigResult<Self> {
+        Self::parse(std::env::args())
+    }
+}
+
+/// Parse a single `key.path=value` override
+pub fn parse_override(arg: &str) -> ConfigResult<(String, Value)> {
+    let (key, raw) = arg
+        .split_once('=')
+        .ok_or_else(|| ConfigError::InvalidFormat(format!("Expected key=value, got '{arg}'")))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(ConfigError::InvalidFormat(format!(
+            "Empty key in override '{arg}'"
+        )));
+    }
+
+    Ok((key.to_string(), parse_value(raw)))
+}
+
+/// Parse an override value as JSON, falling back to a plain string
+///
+/// Numbers that JSON would not write back the same way (`1.10`, `007`) stay
+/// strings. Typed fields still read them as numbers, and `String` fields
+/// also accept plain numbers and booleans, so `--set name=12345` works.
+pub fn parse_value(raw: &str) -> Value {
+    match serde_json::from_str(raw) {
+        Ok(Value::Number(number)) if number.to_string() != raw.trim() => {
+            Value::String(raw.to_string())
+        }
+        Ok(value) => value,
+        Err(_) => Value::String(raw.to_string()),
+    }
+}
+
+/// Ready-made clap arguments for `--config` and `--set`
+///
+/// Flatten into your own parser and convert with [`ConfigArgs::into_cli_args`]:
+///
+/// ```rust
+/// use clap::Parser;
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     config: rusty_config::cli::ConfigArgs,
+/// }
+///
+/// let cli = Cli::parse_from(["app", "--set", "server.port=9090"]);
+/// let args = cli.config.into_cli_args()?;
+/// assert_eq!(args.overrides.len(), 1);
+/// # Ok::<(), rusty_config::ConfigError>(())
+/// ```
+#[cfg(feature = "clap")]
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ConfigArgs {
+    /// Path to the config file
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Override a config value, e.g. `--set server.port=9090`
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+}
+
+#[cfg(feature = "clap")]
+impl ConfigArgs {
+    /// Convert the parsed flags into [`CliArgs`]
+    pub fn into_cli_args(self) -> ConfigResult<CliArgs> {
+        Ok(CliArgs {
+            config: self.config,
+            overrides: self
+                .set
+                .iter()
+                .map(|arg| parse_override(arg))
+                .collect::<ConfigResult<_>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_args() {
+        let args = CliArgs::parse([
+            "app",
+            "--verbose",
+            "--config=app.toml",
+            "--set",
+            "server.port=9090",
+            "--set=server.hosts=[\"a\", \"b\"]",
+            "--set",
+            "name=plain text",
+        ])
+        .unwrap();
+
+        assert_eq!(args.config, Some(PathBuf::from("app.toml")));
+        assert_eq!(
+            args.overrides,
+            vec![
+                ("server.port".to_string(), json!(9090)),
+                ("server.hosts".to_string(), json!(["a", "b"])),
+                ("name".to_string(), json!("plain text")),
+            ]
+        );
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Release {
+        name: String,
+        version: String,
+        ratio: f64,
+    }
+
+    #[test]
+    fn test_numeric_looking_values_on_string_fields() {
+        let args = CliArgs::parse([
+            "app",
+            "--set",
+            "name=12345",
+            "--set",
+            "version=1.10",
+            "--set",
+            "ratio=0.50",
+        ])
+        .unwrap();
+        let mut tree = json!({});
+        for (path, value) in args.overrides {
+            crate::value::insert_path(&mut tree, &path, value);
+        }
+
+        let release: Release = crate::value::from_value_lenient(tree, "Config").unwrap();
+        assert_eq!(release.name, "12345");
+        assert_eq!(release.version, "1.10");
+        assert_eq!(release.ratio, 0.5);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(CliArgs::parse(["app", "--set"]).is_err());
+        assert!(CliArgs::parse(["app", "--set", "no-equals"]).is_err());
+        assert!(CliArgs::parse(["app", "--set", "=1"]).is_err());
+    }
+}
@@ -1,18 +1,23 @@
-use crate::ConfigResult;
+use crate::loader::{ConfigFormat, LoadOptions};
+use crate::validator::ValidationError;
+use crate::watcher::{ConfigEvent, ReloadSender};
+use crate::{ConfigError, ConfigResult};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
     time::SystemTime,
 };
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
 /// Main configuration struct with hot-reload support
 pub struct Config<T> {
     inner: Arc<RwLock<crate::watcher::ConfigData<T>>>,
-    file_path: PathBuf,
-    reload_tx: broadcast::Sender<T>,
+    file_path: Option<PathBuf>,
+    options: Arc<LoadOptions>,
+    reload_tx: ReloadSender<T>,
     watcher_id: Uuid,
 }
 
@@ -22,22 +27,68 @@ where
 {
     /// Create a new config from a file
     pub async fn from_file<P: Into<PathBuf>>(path: P) -> ConfigResult<Self> {
+        Self::from_file_with_options(path, LoadOptions::default()).await
+    }
+
+    /// Create a new config from a file with load options
+    ///
+    /// The options are kept and applied again on every reload.
+    pub async fn from_file_with_options<P: Into<PathBuf>>(
+        path: P,
+        options: LoadOptions,
+    ) -> ConfigResult<Self> {
         let path = path.into();
-        let data = crate::loader::load_from_file(&path).await?;
-        let (reload_tx, _reload_rx) = broadcast::channel(100);
+        let loaded = crate::loader::load_with_sources(&path, &options).await?;
+        let mut config = Self::from_data(loaded.data, Some(path));
+        {
+            let mut inner = config.inner.write().unwrap();
+            inner.warnings = loaded.warnings;
+            inner.sources = loaded.sources;
+        }
+        config.options = Arc::new(options);
+        Ok(config)
+    }
+
+    /// Create a new config from in-memory content
+    ///
+    /// Pass [`ConfigFormat::Unknown`] to detect the format from the content.
+    /// The config has no file path, so [`save`](Self::save) and
+    /// [`reload`](Self::reload) fail; use [`save_to`](Self::save_to) instead.
+    pub fn from_str(content: &str, format: ConfigFormat) -> ConfigResult<Self> {
+        let data = crate::loader::load_from_bytes(content.as_bytes().to_vec(), &format)?;
+        Ok(Self::from_data(data, None))
+    }
+
+    /// Create a new config by reading a reader to the end
+    pub async fn from_reader<R>(mut reader: R, format: ConfigFormat) -> ConfigResult<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let data = crate::loader::load_from_bytes(bytes, &format)?;
+        Ok(Self::from_data(data, None))
+    }
+
+    /// Create a new config from standard input, for CLI tools that pipe configuration
+    pub async fn from_stdin(format: ConfigFormat) -> ConfigResult<Self> {
+        Self::from_reader(tokio::io::stdin(), format).await
+    }
 
-        let config = Self {
+    fn from_data(data: T, file_path: Option<PathBuf>) -> Self {
+        Self {
             inner: Arc::new(RwLock::new(crate::watcher::ConfigData {
                 data,
                 last_modified: SystemTime::now(),
                 version: 1,
+                warnings: Vec::new(),
+                sources: Vec::new(),
             })),
-            file_path: path,
-            reload_tx,
+            file_path,
+            options: Arc::new(LoadOptions::default()),
+            reload_tx: ReloadSender::new(),
             watcher_id: Uuid::new_v4(),
-        };
-
-        Ok(config)
+        }
     }
 
     /// Create a new config with hot-reload
@@ -48,12 +99,13 @@ where
     }
 
     /// Start the file watcher for hot-reload
-    async fn start_watcher(&mut self) -> ConfigResult<()> {
+    pub(crate) async fn start_watcher(&mut self) -> ConfigResult<()> {
         crate::watcher::start_watcher(
-            self.file_path.clone(),
+            self.require_file_path()?.to_path_buf(),
             self.watcher_id,
             Arc::clone(&self.inner),
             self.reload_tx.clone(),
+            Arc::clone(&self.options),
         )
         .await?;
         Ok(())
@@ -61,17 +113,20 @@ where
 
     /// Reload the config from file
     pub async fn reload(&mut self) -> ConfigResult<()> {
-        let new_data = crate::loader::load_from_file(&self.file_path).await?;
+        let loaded =
+            crate::loader::load_with_sources::<T>(self.require_file_path()?, &self.options).await?;
 
         {
             let mut inner = self.inner.write().unwrap();
-            inner.data = new_data;
+            inner.data = loaded.data.clone();
             inner.last_modified = SystemTime::now();
             inner.version += 1;
+            inner.warnings = loaded.warnings.clone();
+            inner.sources = loaded.sources;
         }
 
         // Notify all listeners about the change
-        let _ = self.reload_tx.send(self.get().clone());
+        self.reload_tx.publish(loaded.data, &loaded.warnings);
         Ok(())
     }
 
@@ -81,12 +136,12 @@ where
     }
 
     /// Get the current config as a reference
-    pub fn get_ref(&self) -> std::sync::RwLockReadGuard<crate::watcher::ConfigData<T>> {
+    pub fn get_ref(&self) -> std::sync::RwLockReadGuard<'_, crate::watcher::ConfigData<T>> {
         self.inner.read().unwrap()
     }
 
     /// Get the config as a mutable reference
-    pub fn get_mut(&mut self) -> std::sync::RwLockWriteGuard<crate::watcher::ConfigData<T>> {
+    pub fn get_mut(&mut self) -> std::sync::RwLockWriteGuard<'_, crate::watcher::ConfigData<T>> {
         self.inner.write().unwrap()
     }
 
@@ -95,6 +150,25 @@ where
         self.reload_tx.subscribe()
     }
 
+    /// Get a stream for config changes and the warnings of each reload
+    pub fn watch_events(&self) -> broadcast::Receiver<ConfigEvent<T>> {
+        self.reload_tx.subscribe_events()
+    }
+
+    /// Get the warnings of the last load, e.g. deprecated keys
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        self.inner.read().unwrap().warnings.clone()
+    }
+
+    /// Get the file the config was loaded from, if any
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file_path.as_deref()
+    }
+
+    fn require_file_path(&self) -> ConfigResult<&Path> {
+        self.file_path().ok_or(ConfigError::NoFilePath)
+    }
+
     /// Get the version number of the current config
     pub fn version(&self) -> u64 {
         self.inner.read().unwrap().version
@@ -107,14 +181,101 @@ where
 
     /// Save the config to file
     pub async fn save(&self) -> ConfigResult<()> {
-        let data = self.get();
-        crate::loader::save_to_file(&self.file_path, &data).await
+        self.save_data(self.require_file_path()?).await
     }
 
     /// Save the config to another file
+    ///
+    /// The format is taken from the file extension. Use
+    /// [`convert_to`](Self::convert_to) to learn what the format cannot hold.
     pub async fn save_to<P: Into<PathBuf>>(&self, path: P) -> ConfigResult<()> {
+        self.save_data(&path.into()).await
+    }
+
+    /// Save the config to a file in the format of its extension, see [`crate::convert`]
+    ///
+    /// The file content is built like [`save`](Self::save) builds it, with
+    /// secret references, ciphertexts and the schema version restored. Fails
+    /// without writing anything if the format cannot hold the config; the
+    /// report lists values that do not read back unchanged.
+    pub async fn convert_to<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> ConfigResult<crate::convert::ConversionReport> {
+        let path = path.as_ref();
+        let tree = self.saved_tree(&self.get()).await?;
+        let conversion =
+            crate::convert::convert_value(&tree, &crate::convert::output_format(path))?;
+        crate::convert::write_output(path, &conversion.content).await?;
+        Ok(conversion.report)
+    }
+
+    async fn save_data(&self, target: &Path) -> ConfigResult<()> {
         let data = self.get();
-        crate::loader::save_to_file(&path.into(), &data).await
+        if !self.options.needs_previous_on_save() && self.options.migrations.is_none() {
+            return crate::loader::save_to_file(target, &data).await;
+        }
+        let tree = self.saved_tree(&data).await?;
+        crate::loader::save_to_file(target, &tree).await
+    }
+
+    /// The value tree to write for `data`
+    ///
+    /// Secret references and unchanged ciphertexts of the loaded file are
+    /// kept, secrets are encrypted and the schema version is stamped.
+    async fn saved_tree(&self, data: &T) -> ConfigResult<crate::value::Value> {
+        // The file as it was loaded, to keep secret references and ciphertexts
+        let previous: Option<crate::value::Value> = match self.file_path() {
+            Some(path) if path.exists() && self.options.needs_previous_on_save() => {
+                crate::loader::load_from_file(path).await.ok()
+            }
+            _ => None,
+        };
+
+        let mut tree = crate::secret::expose_secrets(|| serde_json::to_value(data))?;
+        #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+        let references = match &previous {
+            Some(previous) => {
+                crate::resolver::restore_references(&mut tree, previous, &self.options.resolvers)
+                    .await
+            }
+            None => Vec::new(),
+        };
+
+        #[cfg(feature = "encryption")]
+        if let Some(keyring) = &self.options.keyring {
+            crate::encryption::encrypt_secrets(
+                &mut tree,
+                data,
+                keyring,
+                previous.as_ref(),
+                &references,
+            )?;
+        }
+
+        if let Some(migrations) = &self.options.migrations {
+            migrations.stamp(&mut tree);
+        }
+        Ok(tree)
+    }
+
+    /// Get the current config as a value tree with all [`Secret`](crate::Secret) values redacted
+    pub fn redacted(&self) -> ConfigResult<crate::value::Value> {
+        crate::secret::redacted_value(&self.get())
+    }
+
+    /// Render the current config in a text format with all secrets redacted
+    ///
+    /// Safe for logs and diagnostics, e.g. to print the effective configuration
+    /// after defaults and overrides were applied.
+    pub fn dump_redacted(&self, format: ConfigFormat) -> ConfigResult<String> {
+        if format.is_binary() {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Cannot dump config as binary format {format:?}"
+            )));
+        }
+        let bytes = crate::loader::save_to_bytes(&self.redacted()?, &format)?;
+        String::from_utf8(bytes).map_err(|e| ConfigError::InvalidFormat(e.to_string()))
     }
 
     /// Validate the current config
@@ -135,6 +296,7 @@ where
         Self {
             inner: Arc::clone(&self.inner),
             file_path: self.file_path.clone(),
+            options: Arc::clone(&self.options),
             reload_tx: self.reload_tx.clone(),
             watcher_id: self.watcher_id,
         }
@@ -154,3 +316,112 @@ where
             .finish()
     }
 }
+
+/// Create a config from a file embedded at compile time
+///
+/// The format is taken from the file extension, the path is resolved
+/// relative to the current source file like with [`include_str!`].
+///
+/// ```rust
+/// use rusty_config::{include_config, Config};
+/// # #[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// # struct AppConfig { server: serde_json::Value }
+///
+/// let defaults: Config<AppConfig> = include_config!("../config.json")?;
+/// # Ok::<(), rusty_config::ConfigError>(())
+/// ```
+#[macro_export]
+macro_rules! include_config {
+    ($path:literal) => {
+        $crate::Config::from_str(
+            include_str!($path),
+            $crate::loader::detect_format(::std::path::Path::new($path))
+                .unwrap_or($crate::loader::ConfigFormat::Unknown),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct TestConfig {
+        name: String,
+        port: u16,
+    }
+
+    #[tokio::test]
+    async fn test_from_str_without_path() {
+        let mut config =
+            Config::<TestConfig>::from_str("name: test\nport: 8080\n", ConfigFormat::Yaml).unwrap();
+        assert_eq!(config.get().port, 8080);
+        assert!(config.file_path().is_none());
+
+        assert!(matches!(config.save().await, Err(ConfigError::NoFilePath)));
+        assert!(matches!(
+            config.reload().await,
+            Err(ConfigError::NoFilePath)
+        ));
+
+        // Saving to an explicit path still works
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        config.save_to(&path).await.unwrap();
+        let reloaded = Config::<TestConfig>::from_file(&path).await.unwrap();
+        assert_eq!(reloaded.get(), config.get());
+    }
+
+    #[tokio::test]
+    async fn test_from_reader_detects_format() {
+        let content: &[u8] = b"{\"name\": \"piped\", \"port\": 9090}";
+        let config = Config::<TestConfig>::from_reader(content, ConfigFormat::Unknown)
+            .await
+            .unwrap();
+        assert_eq!(config.get().name, "piped");
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct DatabaseConfig {
+        user: String,
+        password: crate::Secret<String>,
+    }
+
+    #[tokio::test]
+    async fn test_secrets_redacted_but_saved() {
+        let config = Config::<DatabaseConfig>::from_str(
+            "user: app\npassword: hunter2\n",
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+
+        assert!(!format!("{config:?}").contains("hunter2"));
+        let dump = config.dump_redacted(ConfigFormat::Toml).unwrap();
+        assert!(dump.contains("password = \"***\""));
+        assert!(dump.contains("user = \"app\""));
+        assert!(config.dump_redacted(ConfigFormat::Cbor).is_err());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.json");
+        config.save_to(&path).await.unwrap();
+        let saved = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(saved.contains("hunter2"));
+    }
+
+    #[cfg(feature = "properties")]
+    #[tokio::test]
+    async fn test_convert_to_reports_lossy_values() {
+        let config =
+            Config::<TestConfig>::from_str("name: test\nport: 8080\n", ConfigFormat::Yaml).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.properties");
+        let report = config.convert_to(&path).await.unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].path, "port");
+
+        let reloaded = Config::<TestConfig>::from_file(&path).await.unwrap();
+        assert_eq!(reloaded.get(), config.get());
+    }
+}
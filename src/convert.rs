@@ -0,0 +1,610 @@
+//! Conversion between config formats with a fidelity report
+//!
+//! Formats differ in what they can hold: TOML has no null and needs a table
+//! at the top level, the flat formats (INI, properties, dotenv) carry every
+//! value as a string, and YAML anchors or non-string keys have no equivalent
+//! elsewhere. [`convert`] goes through the value model and reports every
+//! construct that is changed or dropped on the way. If the target format
+//! cannot hold the document at all, it fails before producing any output.
+//!
+//! ```rust
+//! use rusty_config::convert;
+//! use rusty_config::loader::ConfigFormat;
+//!
+//! let yaml = "name: app\nport: 8080\nproxy: ~\n";
+//! let conversion = convert::convert(yaml.as_bytes(), &ConfigFormat::Yaml, &ConfigFormat::Toml)?;
+//! assert_eq!(
+//!     String::from_utf8(conversion.content).unwrap(),
+//!     "name = \"app\"\nport = 8080\n"
+//! );
+//! assert_eq!(conversion.report.issues[0].path, "proxy");
+//!
+//! let list = "- a\n- b\n";
+//! assert!(convert::convert(list.as_bytes(), &ConfigFormat::Yaml, &ConfigFormat::Toml).is_err());
+//! # Ok::<(), rusty_config::ConfigError>(())
+//! ```
+
+use crate::loader::{self, ConfigFormat};
+use crate::value::{child_path, Change, Value};
+use crate::{ConfigError, ConfigResult};
+use std::path::Path;
+
+/// Key the `toml` crate uses for datetimes in untyped values
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+/// How badly a construct is affected by a conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fidelity {
+    /// Converted, but changed or dropped on the way
+    Lossy,
+    /// The target format cannot hold it, the conversion fails
+    Impossible,
+}
+
+/// A construct that does not survive a conversion unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionIssue {
+    /// Dotted key path, empty for the whole document
+    pub path: String,
+    pub fidelity: Fidelity,
+    pub message: String,
+}
+
+impl ConversionIssue {
+    fn lossy(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            fidelity: Fidelity::Lossy,
+            message: message.into(),
+        }
+    }
+
+    fn impossible(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            fidelity: Fidelity::Impossible,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConversionIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Everything that was changed or dropped by a conversion
+#[derive(Debug, Clone, Default)]
+pub struct ConversionReport {
+    pub issues: Vec<ConversionIssue>,
+}
+
+impl ConversionReport {
+    /// Check whether the converted document reads back exactly like the original
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A converted document together with its report
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub content: Vec<u8>,
+    pub report: ConversionReport,
+}
+
+/// Convert raw content from one format into another
+///
+/// With [`ConfigFormat::Unknown`] as source the format is detected from the
+/// content. Fails with [`ConfigError::Conversion`] listing the impossible
+/// constructs if the target format cannot hold the document.
+pub fn convert(content: &[u8], from: &ConfigFormat, to: &ConfigFormat) -> ConfigResult<Conversion> {
+    let (from, mut document) = match from {
+        ConfigFormat::Unknown => loader::detect_content(content)?,
+        from => (
+            from.clone(),
+            loader::load_from_bytes(content.to_vec(), from)?,
+        ),
+    };
+
+    let mut issues = Vec::new();
+    if let (false, Ok(text)) = (from.is_binary(), std::str::from_utf8(content)) {
+        source_issues(text, &from, &mut issues);
+    }
+    if from == ConfigFormat::Toml {
+        datetimes_to_strings(&mut document, "", &mut issues);
+    }
+    convert_document(document, to, issues)
+}
+
+/// Convert a value tree into content of the given format, see [`convert`]
+pub fn convert_value(document: &Value, to: &ConfigFormat) -> ConfigResult<Conversion> {
+    convert_document(document.clone(), to, Vec::new())
+}
+
+/// Convert a file into another file, see [`convert`]
+///
+/// The input format is taken from the file extension or detected from the
+/// content, the output format from `to` or else the output file extension
+/// (JSON if it has none). Nothing is written if the conversion fails.
+pub async fn convert_file(
+    input: &Path,
+    output: &Path,
+    to: Option<ConfigFormat>,
+) -> ConfigResult<ConversionReport> {
+    if !input.exists() {
+        return Err(ConfigError::FileNotFound(
+            input.to_string_lossy().to_string(),
+        ));
+    }
+    let content = tokio::fs::read(input).await?;
+    let from = loader::detect_format(input).unwrap_or(ConfigFormat::Unknown);
+    let to = to.unwrap_or_else(|| output_format(output));
+    let conversion = convert(&content, &from, &to)?;
+    write_output(output, &conversion.content).await?;
+    Ok(conversion.report)
+}
+
+/// Format for an output file from its extension, JSON if it has none
+pub(crate) fn output_format(path: &Path) -> ConfigFormat {
+    match loader::detect_format(path) {
+        Some(ConfigFormat::Unknown) | None => ConfigFormat::Json,
+        Some(format) => format,
+    }
+}
+
+/// Write content to a file, refusing to replace SOPS-encrypted files
+///
+/// Writing plain text over a SOPS file would break its MAC, so every
+/// command that writes a config file should go through this.
+pub async fn write_output(path: &Path, content: &[u8]) -> ConfigResult<()> {
+    #[cfg(feature = "sops")]
+    if crate::sops::is_encrypted_file(path).await {
+        return Err(ConfigError::Encryption(format!(
+            "Refusing to overwrite SOPS-encrypted file '{}' with plain text",
+            path.display()
+        )));
+    }
+    tokio::fs::write(path, content).await?;
+    Ok(())
+}
+
+/// Check, serialize and read back a document
+///
+/// Whatever reads back differently from what was written is reported as lossy.
+fn convert_document(
+    mut document: Value,
+    to: &ConfigFormat,
+    mut issues: Vec<ConversionIssue>,
+) -> ConfigResult<Conversion> {
+    target_issues(&mut document, to, &mut issues);
+    let impossible: Vec<_> = issues
+        .iter()
+        .filter(|issue| issue.fidelity == Fidelity::Impossible)
+        .cloned()
+        .collect();
+    if !impossible.is_empty() {
+        return Err(ConfigError::Conversion(impossible));
+    }
+
+    let content = loader::save_to_bytes(&document, to)?;
+    let read_back: Value = loader::load_from_bytes(content.clone(), to).map_err(|err| {
+        ConfigError::Conversion(vec![ConversionIssue::impossible(
+            "",
+            format!("the {to:?} output cannot be read back: {err}"),
+        )])
+    })?;
+    for change in crate::value::diff(&document, &read_back) {
+        issues.push(ConversionIssue::lossy(change.path(), describe(&change)));
+    }
+
+    Ok(Conversion {
+        content,
+        report: ConversionReport { issues },
+    })
+}
+
+/// Constructs of the target format's data model that the document violates
+///
+/// Nulls in TOML tables are removed from the document, as TOML writers do
+/// for `None` fields.
+fn target_issues(document: &mut Value, to: &ConfigFormat, issues: &mut Vec<ConversionIssue>) {
+    let needs_table = matches!(
+        to,
+        ConfigFormat::Toml
+            | ConfigFormat::Ini
+            | ConfigFormat::Properties
+            | ConfigFormat::Dotenv
+            | ConfigFormat::Hcl
+    );
+    if needs_table && !document.is_object() {
+        issues.push(ConversionIssue::impossible(
+            "",
+            format!(
+                "{to:?} requires a table at the top level, found {}",
+                kind(document)
+            ),
+        ));
+        return;
+    }
+    if *to == ConfigFormat::Toml {
+        toml_issues(document, "", issues);
+    }
+}
+
+fn toml_issues(value: &mut Value, path: &str, issues: &mut Vec<ConversionIssue>) {
+    match value {
+        Value::Object(table) => {
+            table.retain(|key, child| {
+                if child.is_null() {
+                    issues.push(ConversionIssue::lossy(
+                        child_path(path, key),
+                        "TOML has no null, the key is left out",
+                    ));
+                }
+                !child.is_null()
+            });
+            for (key, child) in table.iter_mut() {
+                toml_issues(child, &child_path(path, key), issues);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let item_path = child_path(path, &index.to_string());
+                if item.is_null() {
+                    issues.push(ConversionIssue::impossible(
+                        item_path,
+                        "TOML arrays cannot hold null",
+                    ));
+                } else {
+                    toml_issues(item, &item_path, issues);
+                }
+            }
+        }
+        Value::Number(number) if number.is_u64() && number.as_i64().is_none() => {
+            issues.push(ConversionIssue::impossible(
+                path,
+                format!("{number} does not fit a TOML integer"),
+            ));
+        }
+        _ => {}
+    }
+}
+
+/// Replace the datetimes of a TOML document with their text
+fn datetimes_to_strings(value: &mut Value, path: &str, issues: &mut Vec<ConversionIssue>) {
+    let datetime = match value {
+        Value::Object(table) if table.len() == 1 => table.get(TOML_DATETIME).cloned(),
+        _ => None,
+    };
+    if let Some(datetime) = datetime {
+        issues.push(ConversionIssue::lossy(
+            path,
+            "TOML datetime is converted to a string",
+        ));
+        *value = datetime;
+        return;
+    }
+    match value {
+        Value::Object(table) => {
+            for (key, child) in table.iter_mut() {
+                datetimes_to_strings(child, &child_path(path, key), issues);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                datetimes_to_strings(item, &child_path(path, &index.to_string()), issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Constructs of the source text that the value model does not keep
+fn source_issues(text: &str, from: &ConfigFormat, issues: &mut Vec<ConversionIssue>) {
+    if has_comments(text, from) {
+        issues.push(ConversionIssue::lossy("", "comments are dropped"));
+    }
+    #[cfg(feature = "yaml")]
+    if *from == ConfigFormat::Yaml {
+        yaml_issues(text, issues);
+    }
+}
+
+/// Check whether config text has comments, which conversions drop
+///
+/// INI and properties files only have comments on their own line, so a `#`
+/// inside a value like `url=http://host/#frag` is not one. For the other
+/// text formats, quoted strings are skipped; binary formats and JSON never
+/// have comments.
+pub fn has_comments(text: &str, format: &ConfigFormat) -> bool {
+    let (line_markers, inline_hash): (&[&str], bool) = match format {
+        ConfigFormat::Ini => (&[";", "#"], false),
+        ConfigFormat::Properties => (&["#", "!"], false),
+        ConfigFormat::Hcl => (&["//", "/*"], true),
+        ConfigFormat::Yaml | ConfigFormat::Toml | ConfigFormat::Dotenv => (&[], true),
+        _ => return false,
+    };
+    text.lines().any(|line| {
+        let trimmed = line.trim_start();
+        line_markers
+            .iter()
+            .any(|marker| trimmed.starts_with(marker))
+            || inline_hash
+                && unquoted(line).any(|(index, c)| {
+                    c == '#' && line[..index].chars().last().is_none_or(char::is_whitespace)
+                })
+    })
+}
+
+/// Characters of a line outside of single and double quotes
+fn unquoted(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    line.char_indices().filter(move |&(_, c)| {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => return true,
+        }
+        false
+    })
+}
+
+/// YAML anchors, merge keys and non-string keys
+#[cfg(feature = "yaml")]
+fn yaml_issues(text: &str, issues: &mut Vec<ConversionIssue>) {
+    for (number, line) in text.lines().enumerate() {
+        let line = match unquoted(line).find(|&(index, c)| {
+            c == '#' && line[..index].chars().last().is_none_or(char::is_whitespace)
+        }) {
+            Some((comment, _)) => &line[..comment],
+            None => line,
+        };
+        for (index, _) in unquoted(line).filter(|&(_, c)| c == '&') {
+            let follows_separator = line[..index]
+                .chars()
+                .last()
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '[' | '{' | ','));
+            let name: String = line[index + 1..]
+                .chars()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}'))
+                .collect();
+            if follows_separator && !name.is_empty() {
+                issues.push(ConversionIssue::lossy(
+                    "",
+                    format!(
+                        "line {}: anchor '&{name}' is dropped, its aliases become copies",
+                        number + 1
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let Ok(document) = serde_yaml::from_str::<serde_yaml::Value>(text) {
+        yaml_key_issues(&document, "", issues);
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_key_issues(value: &serde_yaml::Value, path: &str, issues: &mut Vec<ConversionIssue>) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, child) in mapping {
+                let key_text = match key {
+                    serde_yaml::Value::String(text) => text.clone(),
+                    other => {
+                        let text = serde_yaml::to_string(other)
+                            .map(|text| text.trim_end().to_string())
+                            .unwrap_or_default();
+                        issues.push(ConversionIssue::lossy(
+                            child_path(path, &text),
+                            format!("{} key becomes a string", yaml_kind(other)),
+                        ));
+                        text
+                    }
+                };
+                if key_text == "<<" {
+                    issues.push(ConversionIssue::lossy(
+                        child_path(path, &key_text),
+                        "merge key is kept as a plain '<<' key",
+                    ));
+                }
+                yaml_key_issues(child, &child_path(path, &key_text), issues);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                yaml_key_issues(item, &child_path(path, &index.to_string()), issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_kind(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "boolean",
+        serde_yaml::Value::Number(_) => "number",
+        _ => "complex",
+    }
+}
+
+/// Describe a value that reads back differently after conversion
+fn describe(change: &Change) -> String {
+    match change {
+        Change::Added { value, .. } => format!("{value} appears when read back"),
+        Change::Removed { value, .. } => format!("{value} is dropped"),
+        Change::Changed { old, new, .. }
+            if !old.is_string()
+                && new.as_str() == Some(crate::value::scalar_to_string(old).as_str()) =>
+        {
+            format!("{} {old} is read back as a string", kind(old))
+        }
+        Change::Changed { old, new, .. } => format!("{old} is read back as {new}"),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "table",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn issues(content: &str, from: ConfigFormat, to: ConfigFormat) -> Vec<String> {
+        convert(content.as_bytes(), &from, &to)
+            .unwrap()
+            .report
+            .issues
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[test]
+    fn test_convert_yaml_to_toml() {
+        let conversion = convert(
+            b"# App\nname: app\nproxy: ~\nports: [80, 443]\n",
+            &ConfigFormat::Yaml,
+            &ConfigFormat::Toml,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(conversion.content).unwrap(),
+            "name = \"app\"\nports = [\n    80,\n    443,\n]\n"
+        );
+        let issues: Vec<_> = conversion
+            .report
+            .issues
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "comments are dropped",
+                "proxy: TOML has no null, the key is left out"
+            ]
+        );
+
+        for content in ["- a\n- b\n", "list: [1, ~]\n"] {
+            match convert(content.as_bytes(), &ConfigFormat::Yaml, &ConfigFormat::Toml) {
+                Err(ConfigError::Conversion(issues)) => {
+                    assert!(issues
+                        .iter()
+                        .all(|issue| issue.fidelity == Fidelity::Impossible))
+                }
+                other => panic!("expected a conversion error, got {other:?}"),
+            }
+        }
+    }
+
+    #[cfg(all(feature = "yaml", feature = "json"))]
+    #[test]
+    fn test_convert_reports_yaml_only_constructs() {
+        let content = "\
+base: &base
+  retries: 3
+service:
+  <<: *base
+  url: \"http://a/#&b\"
+1: one
+";
+        assert_eq!(
+            issues(content, ConfigFormat::Yaml, ConfigFormat::Json),
+            [
+                "line 1: anchor '&base' is dropped, its aliases become copies",
+                "service.<<: merge key is kept as a plain '<<' key",
+                "1: number key becomes a string",
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "toml", feature = "ini"))]
+    #[test]
+    fn test_convert_to_flat_format_reports_types() {
+        assert_eq!(
+            issues(
+                "created = 1979-05-27T07:32:00Z\n[server]\nport = 8080\nname = \"a # b\"\n",
+                ConfigFormat::Toml,
+                ConfigFormat::Ini
+            ),
+            [
+                "created: TOML datetime is converted to a string",
+                "server.port: number 8080 is read back as a string",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_has_comments() {
+        let properties = ConfigFormat::Properties;
+        assert!(!has_comments("url=http://host/#frag\n", &properties));
+        assert!(!has_comments("key = a #b\n", &properties));
+        assert!(has_comments("# header\nkey=a\n", &properties));
+        assert!(has_comments("  ! note\n", &properties));
+
+        assert!(!has_comments(
+            "[a]\nurl = http://host/ #frag\n",
+            &ConfigFormat::Ini
+        ));
+        assert!(has_comments("; note\n[a]\n", &ConfigFormat::Ini));
+        assert!(has_comments("# note\n[a]\n", &ConfigFormat::Ini));
+
+        assert!(has_comments("port: 80 # http\n", &ConfigFormat::Yaml));
+        assert!(!has_comments(
+            "url: \"http://a/ #b\"\n",
+            &ConfigFormat::Yaml
+        ));
+        assert!(!has_comments("{\"a\": \"# b\"}", &ConfigFormat::Json));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_convert_value_lossless() {
+        let document = json!({"a": [1, {"b": null}], "c": "text"});
+        for format in [ConfigFormat::Json, ConfigFormat::Yaml] {
+            let conversion = convert_value(&document, &format).unwrap();
+            assert!(conversion.report.is_lossless(), "{format:?}");
+        }
+    }
+
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[tokio::test]
+    async fn test_convert_file_writes_nothing_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("list.yaml");
+        let output = dir.path().join("list.toml");
+        tokio::fs::write(&input, "- a\n").await.unwrap();
+
+        assert!(convert_file(&input, &output, None).await.is_err());
+        assert!(!output.exists());
+
+        tokio::fs::write(&input, "a: 1\n").await.unwrap();
+        let report = convert_file(&input, &output, None).await.unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(tokio::fs::read_to_string(&output).await.unwrap(), "a = 1\n");
+    }
+}
@@ -0,0 +1,267 @@
+//! Support for `.env` files
+//!
+//! A dotenv file can be used directly as a config source, in which case keys
+//! are mapped to nested paths by lowercasing them and splitting on `__`
+//! (`SERVER__PORT=8080` becomes `server.port`), or loaded into the process
+//! environment with [`load_into_env`].
+//!
+//! Supported syntax:
+//!
+//! - `KEY=value`, optionally prefixed with `export`
+//! - `# comments` on their own line or after unquoted values
+//! - single quotes (literal), double quotes (escapes and expansion)
+//! - double-quoted values spanning multiple lines
+//! - `${VAR}` and `${VAR:-default}` expansion from earlier keys and the environment
+
+use crate::value::{self, Value};
+use crate::{ConfigError, ConfigResult};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Separator between nesting levels in dotenv keys
+pub const NESTING_SEPARATOR: &str = "__";
+
+/// Parse dotenv content into key/value pairs in file order
+pub fn parse(content: &str) -> ConfigResult<Vec<(String, String)>> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut defined: HashMap<String, String> = HashMap::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .map(str::trim_start)
+            .unwrap_or(line);
+
+        let (key, raw) = line
+            .split_once('=')
+            .ok_or_else(|| invalid_line(number, "expected KEY=value"))?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(invalid_line(number, &format!("invalid key '{key}'")));
+        }
+
+        let raw = raw.trim_start();
+        let value = match raw.chars().next() {
+            Some('\'') => quoted(raw, '\'', &mut lines).ok_or_else(|| unterminated(number))?,
+            Some('"') => {
+                let inner = quoted(raw, '"', &mut lines).ok_or_else(|| unterminated(number))?;
+                expand(&inner, &defined, true)
+            }
+            _ => {
+                let unquoted = match raw.find(" #") {
+                    Some(index) => &raw[..index],
+                    None => raw,
+                };
+                expand(unquoted.trim_end(), &defined, false)
+            }
+        };
+
+        defined.insert(key.to_string(), value.clone());
+        entries.push((key.to_string(), value));
+    }
+
+    Ok(entries)
+}
+
+/// Parse dotenv content into a nested value tree
+pub fn to_tree(content: &str) -> ConfigResult<Value> {
+    let mut root = Value::Object(Default::default());
+    for (key, value) in parse(content)? {
+        value::insert_path(&mut root, &key_to_path(&key), Value::String(value));
+    }
+    value::normalize_arrays(&mut root);
+    Ok(root)
+}
+
+/// Render a value tree as dotenv content
+pub fn from_tree(tree: &Value) -> String {
+    let mut content = String::new();
+    for (path, leaf) in value::flatten(tree) {
+        content.push_str(&path_to_key(&path));
+        content.push('=');
+        content.push_str(&quote(&value::scalar_to_string(&leaf)));
+        content.push('\n');
+    }
+    content
+}
+
+/// Load a dotenv file into the process environment
+///
+/// Variables that are already set are left untouched, so the real
+/// environment always wins over the file.
+pub async fn load_into_env<P: AsRef<Path>>(path: P) -> ConfigResult<()> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(ConfigError::FileNotFound(
+            path.to_string_lossy().to_string(),
+        ));
+    }
+
+    let content = tokio::fs::read_to_string(path).await?;
+    for (key, value) in parse(&content)? {
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Map a dotenv key to a dotted config path (`SERVER__PORT` -> `server.port`)
+pub fn key_to_path(key: &str) -> String {
+    key.to_lowercase().replace(NESTING_SEPARATOR, ".")
+}
+
+/// Map a dotted config path to a dotenv key (`server.port` -> `SERVER__PORT`)
+pub fn path_to_key(path: &str) -> String {
+    path.to_uppercase().replace('.', NESTING_SEPARATOR)
+}
+
+/// Read a quoted value, consuming further lines until the closing quote
+fn quoted<'a>(
+    raw: &str,
+    quote: char,
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Option<String> {
+    let mut buffer = raw[1..].to_string();
+    loop {
+        if let Some(end) = closing_quote(&buffer, quote) {
+            buffer.truncate(end);
+            return Some(buffer);
+        }
+        let (_, next) = lines.next()?;
+        buffer.push('\n');
+        buffer.push_str(next);
+    }
+}
+
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` references, optionally processing escapes
+///
+/// With escapes enabled (double-quoted values) `\$` produces a literal `$`.
+fn expand(text: &str, defined: &HashMap<String, String>, escapes: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let reference: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                result.push_str(&resolve(&reference, defined));
+            }
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+fn resolve(reference: &str, defined: &HashMap<String, String>) -> String {
+    let (name, default) = match reference.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (reference, None),
+    };
+    defined
+        .get(name)
+        .cloned()
+        .or_else(|| std::env::var(name).ok())
+        .filter(|value| !value.is_empty() || default.is_none())
+        .or_else(|| default.map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn quote(text: &str) -> String {
+    let needs_quotes = text.is_empty()
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '#' | '$' | '\\' | '='));
+    if !needs_quotes {
+        return text.to_string();
+    }
+
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+fn invalid_line(number: usize, message: &str) -> ConfigError {
+    ConfigError::InvalidFormat(format!("dotenv line {}: {message}", number + 1))
+}
+
+fn unterminated(number: usize) -> ConfigError {
+    invalid_line(number, "unterminated quoted value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_syntax() {
+        let content = r#"
+# comment
+export APP_NAME=demo
+PLAIN = value with spaces # trailing comment
+SINGLE='literal ${APP_NAME}'
+DOUBLE="hello ${APP_NAME}\tand ${MISSING:-fallback} \${APP_NAME}"
+MULTI="first
+second"
+"#;
+
+        let entries: HashMap<_, _> = parse(content).unwrap().into_iter().collect();
+        assert_eq!(entries["APP_NAME"], "demo");
+        assert_eq!(entries["PLAIN"], "value with spaces");
+        assert_eq!(entries["SINGLE"], "literal ${APP_NAME}");
+        assert_eq!(entries["DOUBLE"], "hello demo\tand fallback ${APP_NAME}");
+        assert_eq!(entries["MULTI"], "first\nsecond");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("NO_EQUALS_SIGN").is_err());
+        assert!(parse("OPEN=\"never closed").is_err());
+    }
+
+    #[test]
+    fn test_tree_roundtrip() {
+        let content = "SERVER__HOST=localhost\nSERVER__PORT=8080\nSERVER__TAGS__0=a b\n";
+        let tree = to_tree(content).unwrap();
+        assert_eq!(
+            tree,
+            json!({"server": {"host": "localhost", "port": "8080", "tags": ["a b"]}})
+        );
+
+        assert_eq!(to_tree(&from_tree(&tree)).unwrap(), tree);
+    }
+}
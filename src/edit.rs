@@ -0,0 +1,441 @@
+//! Format-preserving edits of config files
+//!
+//! [`set_value`] changes a single value in the text of a config file. TOML
+//! and YAML files keep their comments, key order and layout; other formats,
+//! and YAML constructs the editor does not handle (flow collections, lists
+//! of tables), are re-serialized from the parsed document instead.
+//!
+//! ```rust
+//! use rusty_config::edit;
+//! use rusty_config::loader::ConfigFormat;
+//!
+//! let content = "# Server settings\nserver:\n  port: 80 # http\n  host: localhost\n";
+//! let edited = edit::set_value(
+//!     content.as_bytes(),
+//!     &ConfigFormat::Yaml,
+//!     "server.port",
+//!     8080.into(),
+//! )?;
+//! assert!(!edited.rewritten);
+//! assert_eq!(
+//!     String::from_utf8(edited.content).unwrap(),
+//!     "# Server settings\nserver:\n  port: 8080 # http\n  host: localhost\n"
+//! );
+//! # Ok::<(), rusty_config::ConfigError>(())
+//! ```
+
+use crate::convert;
+use crate::loader::{self, ConfigFormat};
+use crate::value::{self, Value};
+use crate::ConfigResult;
+use std::path::Path;
+
+/// Result of an edit
+#[derive(Debug, Clone)]
+pub struct Edited {
+    /// The new file content
+    pub content: Vec<u8>,
+    /// Whether the file was re-serialized, losing comments and formatting
+    pub rewritten: bool,
+}
+
+/// Set the value at a dotted key path in the content of a config file
+///
+/// Missing tables along the path are created. The edited text is parsed
+/// again and only kept if it yields exactly the expected document.
+pub fn set_value(
+    content: &[u8],
+    format: &ConfigFormat,
+    path: &str,
+    new_value: Value,
+) -> ConfigResult<Edited> {
+    let format = match format {
+        ConfigFormat::Unknown => loader::detect_content(content)?.0,
+        format => format.clone(),
+    };
+    let mut expected: Value = loader::load_from_bytes(content.to_vec(), &format)?;
+    value::insert_path(&mut expected, path, new_value.clone());
+
+    let text = std::str::from_utf8(content).ok();
+    let edited: Option<String> = match (&format, text) {
+        #[cfg(feature = "toml")]
+        (ConfigFormat::Toml, Some(text)) => set_toml(text, path, &new_value),
+        #[cfg(feature = "yaml")]
+        (ConfigFormat::Yaml, Some(text)) => set_yaml(text, path, &new_value),
+        _ => None,
+    };
+    if let Some(edited) = edited {
+        let reparsed = loader::load_from_bytes::<Value>(edited.clone().into_bytes(), &format);
+        if reparsed.is_ok_and(|reparsed| reparsed == expected) {
+            return Ok(Edited {
+                content: edited.into_bytes(),
+                rewritten: false,
+            });
+        }
+    }
+
+    Ok(Edited {
+        content: loader::save_to_bytes(&expected, &format)?,
+        rewritten: true,
+    })
+}
+
+/// Set a value in a config file, see [`set_value`]
+///
+/// The format is taken from the file extension, or detected from the
+/// content for files without a known extension. SOPS-encrypted files are
+/// refused, see [`convert::write_output`].
+pub async fn set_in_file(path: &Path, key_path: &str, new_value: Value) -> ConfigResult<Edited> {
+    let content = tokio::fs::read(path).await?;
+    let format = loader::detect_format(path).unwrap_or(ConfigFormat::Unknown);
+    let edited = set_value(&content, &format, key_path, new_value)?;
+    convert::write_output(path, &edited.content).await?;
+    Ok(edited)
+}
+
+/// Edit a TOML document in place, `None` if the path cannot be edited
+#[cfg(feature = "toml")]
+fn set_toml(text: &str, path: &str, new_value: &Value) -> Option<String> {
+    let mut document: toml_edit::DocumentMut = text.parse().ok()?;
+    let segments: Vec<&str> = path.split('.').filter(|s| !s.is_empty()).collect();
+    let (last, parents) = segments.split_last()?;
+
+    let mut item = document.as_item_mut();
+    for segment in parents {
+        let table = item.as_table_like_mut()?;
+        if !table.contains_key(segment) {
+            let mut child = toml_edit::Table::new();
+            child.set_implicit(true);
+            table.insert(segment, toml_edit::Item::Table(child));
+        }
+        item = table.get_mut(segment)?;
+    }
+
+    let table = item.as_table_like_mut()?;
+    let mut replacement = toml_value(new_value)?;
+    match table.get_mut(last) {
+        Some(existing) => {
+            // Keep comments around the old value
+            if let Some(old) = existing.as_value() {
+                *replacement.decor_mut() = old.decor().clone();
+            }
+            *existing = toml_edit::Item::Value(replacement);
+        }
+        None => {
+            table.insert(last, toml_edit::Item::Value(replacement));
+        }
+    }
+    Some(document.to_string())
+}
+
+/// Convert a value for `toml_edit`, `None` for values TOML cannot hold
+#[cfg(feature = "toml")]
+fn toml_value(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => (*b).into(),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => integer.into(),
+            None if number.is_u64() => return None,
+            None => number.as_f64()?.into(),
+        },
+        Value::String(text) => text.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(table) => {
+            let mut inline = toml_edit::InlineTable::new();
+            for (key, child) in table {
+                inline.insert(key, toml_value(child)?);
+            }
+            inline.into()
+        }
+    })
+}
+
+/// Where a key was found in a block of YAML lines
+#[cfg(feature = "yaml")]
+enum Lookup {
+    Found { line: usize, indent: usize },
+    Missing { child_indent: Option<usize> },
+    Unsupported,
+}
+
+#[cfg(feature = "yaml")]
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[cfg(feature = "yaml")]
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---"
+}
+
+/// Find `key:` among the direct children of a block mapping
+#[cfg(feature = "yaml")]
+fn find_key(lines: &[String], start: usize, end: usize, key: &str) -> Lookup {
+    let mut child_indent = None;
+    for (index, line) in lines.iter().enumerate().take(end).skip(start) {
+        if !is_content(line) {
+            continue;
+        }
+        let indent = indent_of(line);
+        let trimmed = &line[indent..];
+        let is_first = child_indent.is_none();
+        let child_indent = *child_indent.get_or_insert(indent);
+        if indent != child_indent {
+            continue;
+        }
+        if trimmed.starts_with(['{', '[', '&', '*', '!', '?'])
+            || is_first && trimmed.starts_with('-')
+        {
+            // Flow collections, anchors, tags and lists
+            return Lookup::Unsupported;
+        }
+        if trimmed.starts_with('-') {
+            // A list written at the same indentation as its key
+            continue;
+        }
+        let matches = [key.to_string(), format!("\"{key}\""), format!("'{key}'")]
+            .iter()
+            .any(|quoted| {
+                trimmed
+                    .strip_prefix(quoted.as_str())
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+            });
+        if matches {
+            return Lookup::Found {
+                line: index,
+                indent,
+            };
+        }
+    }
+    Lookup::Missing { child_indent }
+}
+
+/// End of the block that belongs to the key on line `start`
+#[cfg(feature = "yaml")]
+fn block_end(lines: &[String], start: usize, indent: usize) -> usize {
+    let mut end = start + 1;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        let line_indent = indent_of(line);
+        let same_level_list = line_indent == indent && line[line_indent..].starts_with('-');
+        if line_indent <= indent && !same_level_list {
+            break;
+        }
+        end = index + 1;
+    }
+    end
+}
+
+/// Render a value on a single line
+#[cfg(feature = "yaml")]
+fn yaml_inline(value: &Value) -> Option<String> {
+    match value {
+        Value::String(_) => {
+            let rendered = serde_yaml::to_string(value).ok()?;
+            let rendered = rendered.trim_end();
+            if rendered.contains('\n') {
+                serde_json::to_string(value).ok()
+            } else {
+                Some(rendered.to_string())
+            }
+        }
+        _ => serde_json::to_string(value).ok(),
+    }
+}
+
+/// Edit a YAML document line by line, `None` if the path cannot be edited
+#[cfg(feature = "yaml")]
+fn set_yaml(text: &str, path: &str, new_value: &Value) -> Option<String> {
+    let segments: Vec<&str> = path.split('.').filter(|s| !s.is_empty()).collect();
+    if segments.is_empty() {
+        return None;
+    }
+    let rendered = yaml_inline(new_value)?;
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let (mut start, mut end) = (0, lines.len());
+    let mut parent_indent: Option<usize> = None;
+
+    for (depth, segment) in segments.iter().enumerate() {
+        let is_last = depth + 1 == segments.len();
+        match find_key(&lines, start, end, segment) {
+            Lookup::Unsupported => return None,
+            Lookup::Found { line, indent } => {
+                if parent_indent.is_some_and(|parent| indent <= parent) {
+                    return None;
+                }
+                let key_end = line_key_end(&lines[line], indent)?;
+                let rest = lines[line][key_end..].to_string();
+                let block = block_end(&lines, line, indent);
+                if is_last {
+                    let comment = plain_comment(&rest);
+                    lines[line] = format!("{}: {rendered}{comment}", &lines[line][..key_end - 1]);
+                    lines.drain(line + 1..block);
+                    break;
+                }
+                let inline = rest.trim();
+                if !inline.is_empty() && !inline.starts_with('#') {
+                    return None;
+                }
+                parent_indent = Some(indent);
+                start = line + 1;
+                end = block;
+            }
+            Lookup::Missing { child_indent } => {
+                let indent = child_indent
+                    .or(parent_indent.map(|parent| parent + 2))
+                    .unwrap_or(0);
+                let mut insert_at = end;
+                while insert_at > start && !is_content(&lines[insert_at - 1]) {
+                    insert_at -= 1;
+                }
+                let new_lines = segments[depth..].iter().enumerate().map(|(i, key)| {
+                    let padding = " ".repeat(indent + 2 * i);
+                    if depth + i + 1 == segments.len() {
+                        format!("{padding}{key}: {rendered}")
+                    } else {
+                        format!("{padding}{key}:")
+                    }
+                });
+                lines.splice(insert_at..insert_at, new_lines.collect::<Vec<_>>());
+                break;
+            }
+        }
+    }
+
+    let mut edited = lines.join("\n");
+    if text.ends_with('\n') {
+        edited.push('\n');
+    }
+    Some(edited)
+}
+
+/// Byte position right after the `:` of the key on a line
+#[cfg(feature = "yaml")]
+fn line_key_end(line: &str, indent: usize) -> Option<usize> {
+    let trimmed = &line[indent..];
+    let key_len = match trimmed.chars().next()? {
+        quote @ ('"' | '\'') => trimmed[1..].find(quote)? + 2,
+        _ => trimmed.find(':')?,
+    };
+    Some(indent + key_len + 1)
+}
+
+/// The comment after a plain scalar, with its leading whitespace
+#[cfg(feature = "yaml")]
+fn plain_comment(rest: &str) -> &str {
+    let value = rest.trim_start();
+    if value.starts_with(['"', '\'']) {
+        return "";
+    }
+    match rest.find(" #") {
+        Some(position) => {
+            let before = rest[..position].trim_end().len();
+            &rest[before..]
+        }
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn set(content: &str, format: ConfigFormat, path: &str, value: Value) -> (String, bool) {
+        let edited = set_value(content.as_bytes(), &format, path, value).unwrap();
+        (String::from_utf8(edited.content).unwrap(), edited.rewritten)
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_set_yaml_keeps_comments() {
+        let content = "\
+# App
+server:
+  host: localhost  # bind address
+  tls:
+    cert: a.pem
+  ports:
+  - 80
+  - 443
+
+debug: false
+";
+        assert_eq!(
+            set(content, ConfigFormat::Yaml, "server.host", json!("0.0.0.0")).0,
+            content.replace("localhost  #", "0.0.0.0  #")
+        );
+        assert_eq!(
+            set(content, ConfigFormat::Yaml, "server.ports", json!([8080])).0,
+            content.replace("  ports:\n  - 80\n  - 443\n", "  ports: [8080]\n")
+        );
+        assert_eq!(
+            set(
+                content,
+                ConfigFormat::Yaml,
+                "server.tls.key",
+                json!("b.pem")
+            )
+            .0,
+            content.replace("cert: a.pem\n", "cert: a.pem\n    key: b.pem\n")
+        );
+        assert_eq!(
+            set(content, ConfigFormat::Yaml, "log.level", json!("8080")).0,
+            format!("{content}log:\n  level: '8080'\n")
+        );
+
+        // Lists of tables are re-serialized
+        let (edited, rewritten) = set(
+            "servers:\n- port: 1\n",
+            ConfigFormat::Yaml,
+            "servers.0.port",
+            json!(2),
+        );
+        assert!(rewritten);
+        assert_eq!(edited, "servers:\n- port: 2\n");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_set_toml_keeps_comments() {
+        let content = "\
+# App
+name = \"app\"
+
+[server]
+port = 80 # http
+";
+        let (edited, rewritten) = set(content, ConfigFormat::Toml, "server.port", json!(8080));
+        assert!(!rewritten);
+        assert_eq!(edited, content.replace("80 #", "8080 #"));
+
+        let (edited, _) = set(content, ConfigFormat::Toml, "log.level", json!("debug"));
+        assert_eq!(edited, format!("{content}\n[log]\nlevel = \"debug\"\n"));
+        assert!(set_value(content.as_bytes(), &ConfigFormat::Toml, "name", Value::Null).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_set_json_rewrites() {
+        let (edited, rewritten) = set(
+            r#"{"b": 1, "a": {"x": true}}"#,
+            ConfigFormat::Unknown,
+            "a.x",
+            json!(false),
+        );
+        assert!(rewritten);
+        let edited: Value = serde_json::from_str(&edited).unwrap();
+        assert_eq!(edited, json!({"b": 1, "a": {"x": false}}));
+    }
+}
@@ -0,0 +1,600 @@
+//! Encrypted values inside config files
+//!
+//! Individual values can be stored encrypted with AES-256-GCM:
+//!
+//! ```yaml
+//! database:
+//!   user: app
+//!   password: ENC[AES256_GCM,data:...,iv:...,type:str,kid:v2]
+//! ```
+//!
+//! `data` holds the ciphertext with the authentication tag, `iv` the 96-bit
+//! nonce, both base64. `type` restores the original scalar type (`str`,
+//! `int`, `float`, `bool` or `json`) and `kid` names the key that was used.
+//! The dotted key path is bound to the ciphertext as additional data, so a
+//! value copied to another key fails to decrypt.
+//!
+//! Encrypted values are decrypted transparently at load when a [`Keyring`]
+//! is configured, and re-encrypted on [`Config::save`](crate::Config::save).
+//! Keys are local only: raw or base64 key files, or base64 in an env var.
+
+use crate::value::{child_path, get_path_mut, Value};
+use crate::{ConfigError, ConfigResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Prefix of an encrypted value
+const PREFIX: &str = "ENC[AES256_GCM,";
+
+/// A 256-bit AES key with an identifier
+///
+/// The key bytes are zeroized when dropped.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    id: String,
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl EncryptionKey {
+    /// Create a key from raw bytes
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self {
+            id: id.into(),
+            key: Zeroizing::new(key),
+        }
+    }
+
+    /// Generate a new random key
+    pub fn generate(id: impl Into<String>) -> Self {
+        Self::new(id, Aes256Gcm::generate_key(OsRng).into())
+    }
+
+    /// Create a key from its base64 representation
+    pub fn from_base64(id: impl Into<String>, encoded: &str) -> ConfigResult<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|err| ConfigError::Encryption(format!("Invalid base64 key: {err}")))?;
+        Self::from_bytes(id, &bytes)
+    }
+
+    /// Read a key file containing either 32 raw bytes or the base64 encoding
+    ///
+    /// The key id is the file stem (`keys/v2.key` has the id `v2`).
+    pub async fn from_file<P: AsRef<Path>>(path: P) -> ConfigResult<Self> {
+        let path = path.as_ref();
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "default".to_string());
+        let bytes = tokio::fs::read(path).await?;
+
+        if bytes.len() == 32 {
+            return Self::from_bytes(id, &bytes);
+        }
+        let encoded = String::from_utf8(bytes)
+            .map_err(|_| ConfigError::Encryption("Key file is neither raw nor base64".into()))?;
+        Self::from_base64(id, &encoded)
+    }
+
+    /// Read a base64 key from an environment variable
+    ///
+    /// The key id is the variable name.
+    pub fn from_env(var: &str) -> ConfigResult<Self> {
+        let encoded = std::env::var(var).map_err(|_| {
+            ConfigError::Encryption(format!("Environment variable '{var}' is not set"))
+        })?;
+        Self::from_base64(var, &encoded)
+    }
+
+    fn from_bytes(id: impl Into<String>, bytes: &[u8]) -> ConfigResult<Self> {
+        let key: [u8; 32] = bytes.try_into().map_err(|_| {
+            ConfigError::Encryption(format!("Key must be 32 bytes, got {}", bytes.len()))
+        })?;
+        Ok(Self::new(id, key))
+    }
+
+    /// Get the key identifier
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the base64 representation, e.g. to store a generated key
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(*self.key)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.key.as_slice()))
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Set of keys: one primary key for encryption, older keys for decryption
+///
+/// To rotate keys, make the new key primary and keep the old one with
+/// [`with_previous`](Self::with_previous) until all files are re-encrypted,
+/// e.g. with [`rotate_file`].
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    primary: EncryptionKey,
+    previous: Vec<EncryptionKey>,
+}
+
+impl Keyring {
+    /// Create a keyring with a primary key
+    pub fn new(primary: EncryptionKey) -> Self {
+        Self {
+            primary,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Add an older key that is only used for decryption
+    pub fn with_previous(mut self, key: EncryptionKey) -> Self {
+        self.previous.push(key);
+        self
+    }
+
+    /// Get the key used for encryption
+    pub fn primary(&self) -> &EncryptionKey {
+        &self.primary
+    }
+
+    /// Encrypt the value at dotted key `path` into an `ENC[...]` string
+    pub fn encrypt_value(&self, path: &str, value: &Value) -> ConfigResult<String> {
+        let (plaintext, kind) = match value {
+            Value::String(s) => (s.clone(), "str"),
+            Value::Bool(b) => (b.to_string(), "bool"),
+            Value::Number(n) if n.is_f64() => (n.to_string(), "float"),
+            Value::Number(n) => (n.to_string(), "int"),
+            other => (other.to_string(), "json"),
+        };
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .primary
+            .cipher()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: path.as_bytes(),
+                },
+            )
+            .map_err(|_| ConfigError::Encryption("Encryption failed".to_string()))?;
+
+        Ok(format!(
+            "{PREFIX}data:{},iv:{},type:{kind},kid:{}]",
+            BASE64.encode(ciphertext),
+            BASE64.encode(nonce),
+            self.primary.id
+        ))
+    }
+
+    /// Decrypt the `ENC[...]` string found at dotted key `path` into a typed value
+    pub fn decrypt_value(&self, path: &str, encrypted: &str) -> ConfigResult<Value> {
+        let envelope = Envelope::parse(encrypted)?;
+
+        // Try the key named in the envelope first, then every other key
+        let mut keys: Vec<&EncryptionKey> = std::iter::once(&self.primary)
+            .chain(&self.previous)
+            .collect();
+        keys.sort_by_key(|key| Some(key.id.as_str()) != envelope.kid);
+
+        let plaintext = keys
+            .iter()
+            .find_map(|key| {
+                let payload = Payload {
+                    msg: envelope.data.as_ref(),
+                    aad: path.as_bytes(),
+                };
+                key.cipher()
+                    .decrypt(Nonce::from_slice(&envelope.iv), payload)
+                    .ok()
+            })
+            .ok_or_else(|| {
+                ConfigError::Encryption(
+                    "Decryption failed: no key matches, or the value was tampered with or moved"
+                        .to_string(),
+                )
+            })?;
+        let plaintext = String::from_utf8(plaintext)
+            .map_err(|_| ConfigError::Encryption("Decrypted value is not UTF-8".to_string()))?;
+
+        let parsed = match envelope.kind {
+            "str" => Some(Value::String(plaintext)),
+            "int" | "float" | "bool" | "json" => serde_json::from_str(&plaintext).ok(),
+            _ => None,
+        };
+        parsed.ok_or_else(|| {
+            ConfigError::Encryption(format!(
+                "Cannot restore encrypted value of type '{}'",
+                envelope.kind
+            ))
+        })
+    }
+
+    /// Check whether an encrypted value was produced with the primary key
+    pub fn is_current(&self, encrypted: &str) -> bool {
+        Envelope::parse(encrypted).is_ok_and(|envelope| envelope.kid == Some(&self.primary.id))
+    }
+}
+
+/// Check whether a string is an encrypted value
+pub fn is_encrypted(text: &str) -> bool {
+    text.starts_with(PREFIX) && text.ends_with(']')
+}
+
+/// Decrypt every encrypted value in a tree, returning the paths that were encrypted
+pub fn decrypt_tree(tree: &mut Value, keyring: &Keyring) -> ConfigResult<Vec<String>> {
+    let mut paths = Vec::new();
+    decrypt_into(tree, String::new(), keyring, &mut paths)?;
+    Ok(paths)
+}
+
+fn decrypt_into(
+    value: &mut Value,
+    path: String,
+    keyring: &Keyring,
+    paths: &mut Vec<String>,
+) -> ConfigResult<()> {
+    match value {
+        Value::String(text) if is_encrypted(text) => {
+            *value = keyring
+                .decrypt_value(&path, text)
+                .map_err(|err| ConfigError::Encryption(format!("{path}: {err}")))?;
+            paths.push(path);
+        }
+        Value::Object(table) => {
+            for (key, child) in table.iter_mut() {
+                decrypt_into(child, child_path(&path, key), keyring, paths)?;
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter_mut().enumerate() {
+                decrypt_into(child, child_path(&path, &index.to_string()), keyring, paths)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Prepare a serialized config for writing
+///
+/// Values at `paths` are encrypted unless they already are. When `previous`
+/// holds the file's current content, unchanged values keep their existing
+/// ciphertext as long as it was made with the primary key, so saving does
+/// not rewrite every encrypted value.
+pub fn encrypt_paths(
+    tree: &mut Value,
+    paths: &[String],
+    keyring: &Keyring,
+    previous: Option<&Value>,
+) -> ConfigResult<()> {
+    for path in paths {
+        let Some(current) = get_path_mut(tree, path) else {
+            continue;
+        };
+        if current.as_str().is_some_and(is_encrypted) {
+            continue;
+        }
+
+        let reusable = previous
+            .and_then(|previous| crate::value::get_path(previous, path))
+            .and_then(Value::as_str)
+            .filter(|old| keyring.is_current(old))
+            .filter(|old| keyring.decrypt_value(path, old).ok().as_ref() == Some(&*current));
+        *current = Value::String(match reusable {
+            Some(old) => old.to_string(),
+            None => keyring.encrypt_value(path, current)?,
+        });
+    }
+    Ok(())
+}
+
+/// Save a config, encrypting its [`Secret`](crate::Secret) fields and every
+/// value that is encrypted in `reference` (usually the file it was loaded from)
+pub async fn save_encrypted<T>(
+    target: &Path,
+    data: &T,
+    keyring: &Keyring,
+    reference: Option<&Path>,
+) -> ConfigResult<()>
+where
+    T: Serialize,
+{
+    let mut tree = crate::secret::expose_secrets(|| serde_json::to_value(data))?;
+    let previous: Option<Value> = match reference {
+        Some(path) if path.exists() => crate::loader::load_from_file(path).await.ok(),
+        _ => None,
+    };
+
+    encrypt_secrets(&mut tree, data, keyring, previous.as_ref(), &[])?;
+    crate::loader::save_to_file(target, &tree).await
+}
+
+/// Encrypt the [`Secret`](crate::Secret) fields of `data` and every value
+/// that is encrypted in `previous` within its serialized `tree`
+///
+/// Values at `keep_plain` are left alone, e.g. restored secret references.
+pub fn encrypt_secrets<T>(
+    tree: &mut Value,
+    data: &T,
+    keyring: &Keyring,
+    previous: Option<&Value>,
+    keep_plain: &[String],
+) -> ConfigResult<()>
+where
+    T: Serialize,
+{
+    let mut paths = crate::secret::secret_paths(data)?;
+    if let Some(previous) = previous {
+        paths.extend(
+            crate::value::flatten(previous)
+                .into_iter()
+                .filter(|(_, leaf)| leaf.as_str().is_some_and(is_encrypted))
+                .map(|(path, _)| path),
+        );
+    }
+    paths.retain(|path| !keep_plain.contains(path));
+
+    encrypt_paths(tree, &paths, keyring, previous)
+}
+
+/// Re-encrypt every encrypted value in a file with the keyring's primary key
+///
+/// Returns the number of values that were re-encrypted.
+pub async fn rotate_file<P: AsRef<Path>>(path: P, keyring: &Keyring) -> ConfigResult<usize> {
+    let path = path.as_ref();
+    let mut tree: Value = crate::loader::load_from_file(path).await?;
+    let original = tree.clone();
+
+    let paths = decrypt_tree(&mut tree, keyring)?;
+    let stale: Vec<String> = paths
+        .into_iter()
+        .filter(|path| {
+            crate::value::get_path(&original, path)
+                .and_then(Value::as_str)
+                .is_some_and(|old| !keyring.is_current(old))
+        })
+        .collect();
+
+    // Everything else keeps its ciphertext
+    let mut rotated = original;
+    for path in &stale {
+        if let (Some(target), Some(plain)) = (
+            get_path_mut(&mut rotated, path),
+            crate::value::get_path(&tree, path),
+        ) {
+            *target = Value::String(keyring.encrypt_value(path, plain)?);
+        }
+    }
+
+    if !stale.is_empty() {
+        crate::loader::save_to_file(path, &rotated).await?;
+    }
+    Ok(stale.len())
+}
+
+/// Parsed fields of an `ENC[...]` string
+struct Envelope<'a> {
+    data: Vec<u8>,
+    iv: Vec<u8>,
+    kind: &'a str,
+    kid: Option<&'a str>,
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(text: &'a str) -> ConfigResult<Self> {
+        let invalid = || ConfigError::Encryption("Malformed encrypted value".to_string());
+        let body = text
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.strip_suffix(']'))
+            .ok_or_else(invalid)?;
+
+        let (mut data, mut iv, mut kind, mut kid) = (None, None, "str", None);
+        for field in body.split(',') {
+            match field.split_once(':').ok_or_else(invalid)? {
+                ("data", value) => data = Some(BASE64.decode(value).map_err(|_| invalid())?),
+                ("iv", value) => iv = Some(BASE64.decode(value).map_err(|_| invalid())?),
+                ("type", value) => kind = value,
+                ("kid", value) => kid = Some(value),
+                _ => {}
+            }
+        }
+
+        let iv = iv.filter(|iv| iv.len() == 12).ok_or_else(invalid)?;
+        Ok(Self {
+            data: data.ok_or_else(invalid)?,
+            iv,
+            kind,
+            kid,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_encrypt_decrypt_typed_values() {
+        let keyring = Keyring::new(EncryptionKey::generate("v1"));
+
+        for value in [
+            json!("hunter2"),
+            json!(5432),
+            json!(0.5),
+            json!(true),
+            json!(["a"]),
+        ] {
+            let encrypted = keyring.encrypt_value("db.value", &value).unwrap();
+            assert!(is_encrypted(&encrypted));
+            assert!(encrypted.contains("kid:v1"));
+            assert_eq!(
+                keyring.decrypt_value("db.value", &encrypted).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let keyring = Keyring::new(EncryptionKey::generate("v1"));
+        let other = Keyring::new(EncryptionKey::generate("v1"));
+        let encrypted = keyring
+            .encrypt_value("admin_password", &json!("secret"))
+            .unwrap();
+
+        assert!(other.decrypt_value("admin_password", &encrypted).is_err());
+
+        let tampered = encrypted.replacen("data:", "data:AAAA", 1);
+        assert!(keyring.decrypt_value("admin_password", &tampered).is_err());
+
+        // A ciphertext copied to another key does not decrypt there
+        assert!(keyring.decrypt_value("guest_password", &encrypted).is_err());
+        let mut tree = json!({"admin_password": encrypted, "guest_password": encrypted});
+        assert!(decrypt_tree(&mut tree, &keyring).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tree_with_previous_key() {
+        let old = EncryptionKey::generate("v1");
+        let encrypted = Keyring::new(old.clone())
+            .encrypt_value("db.password", &json!("hunter2"))
+            .unwrap();
+
+        let keyring = Keyring::new(EncryptionKey::generate("v2")).with_previous(old);
+        let mut tree = json!({"db": {"user": "app", "password": encrypted}});
+        let paths = decrypt_tree(&mut tree, &keyring).unwrap();
+
+        assert_eq!(paths, vec!["db.password".to_string()]);
+        assert_eq!(tree, json!({"db": {"user": "app", "password": "hunter2"}}));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_file() {
+        let old = EncryptionKey::generate("v1");
+        let encrypted = Keyring::new(old.clone())
+            .encrypt_value("port", &json!(5432))
+            .unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.json");
+        let content = json!({"port": encrypted, "host": "db"}).to_string();
+        tokio::fs::write(&path, content).await.unwrap();
+
+        let keyring = Keyring::new(EncryptionKey::generate("v2")).with_previous(old);
+        assert_eq!(rotate_file(&path, &keyring).await.unwrap(), 1);
+        assert_eq!(rotate_file(&path, &keyring).await.unwrap(), 0);
+
+        let mut tree: Value = crate::loader::load_from_file(&path).await.unwrap();
+        assert!(keyring.is_current(tree["port"].as_str().unwrap()));
+        decrypt_tree(&mut tree, &Keyring::new(keyring.primary().clone())).unwrap();
+        assert_eq!(tree, json!({"port": 5432, "host": "db"}));
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Database {
+        user: String,
+        port: u16,
+        password: crate::Secret<String>,
+    }
+
+    #[tokio::test]
+    async fn test_config_decrypts_on_load_and_encrypts_on_save() {
+        let keyring = Keyring::new(EncryptionKey::generate("v1"));
+        let encrypted_port = keyring.encrypt_value("port", &json!(5432)).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("db.yaml");
+        let content = format!("user: app\nport: {encrypted_port}\npassword: hunter2\n");
+        tokio::fs::write(&path, content).await.unwrap();
+
+        let config = crate::ConfigBuilder::new()
+            .file(&path)
+            .encryption(keyring.clone())
+            .build::<Database>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().port, 5432);
+        assert_eq!(config.get().password.expose(), "hunter2");
+
+        config.save().await.unwrap();
+        let saved: Value = crate::loader::load_from_file(&path).await.unwrap();
+        assert_eq!(saved["user"], "app");
+        // Unchanged encrypted values keep their ciphertext, secrets get encrypted
+        assert_eq!(saved["port"], json!(encrypted_port));
+        assert!(is_encrypted(saved["password"].as_str().unwrap()));
+
+        let reloaded = crate::Config::<Database>::from_file_with_options(
+            &path,
+            crate::loader::LoadOptions {
+                keyring: Some(std::sync::Arc::new(keyring)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(reloaded.get(), config.get());
+    }
+
+    #[tokio::test]
+    async fn test_convert_keeps_references_and_ciphertexts() {
+        let keyring = Keyring::new(EncryptionKey::generate("v1"));
+        let encrypted_port = keyring.encrypt_value("port", &json!(5432)).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let secret_path = temp_dir.path().join("password.txt");
+        tokio::fs::write(&secret_path, "hunter2").await.unwrap();
+        let reference = format!("file://{}", secret_path.display());
+        let path = temp_dir.path().join("db.yaml");
+        let content = format!("user: app\nport: {encrypted_port}\npassword: {reference}\n");
+        tokio::fs::write(&path, content).await.unwrap();
+
+        let config = crate::ConfigBuilder::new()
+            .file(&path)
+            .encryption(keyring.clone())
+            .resolve_secrets(true)
+            .build::<Database>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().password.expose(), "hunter2");
+
+        let target = temp_dir.path().join("db.json");
+        let report = config.convert_to(&target).await.unwrap();
+        assert!(report.is_lossless(), "{report:?}");
+        let converted = tokio::fs::read_to_string(&target).await.unwrap();
+        assert!(!converted.contains("hunter2"), "{converted}");
+        let converted: Value = serde_json::from_str(&converted).unwrap();
+        assert_eq!(converted["password"], json!(reference));
+        assert_eq!(converted["port"], json!(encrypted_port));
+    }
+
+    #[tokio::test]
+    async fn test_key_sources() {
+        let key = EncryptionKey::generate("generated");
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let path = temp_dir.path().join("v3.key");
+        tokio::fs::write(&path, key.to_base64()).await.unwrap();
+        let from_file = EncryptionKey::from_file(&path).await.unwrap();
+        assert_eq!(from_file.id(), "v3");
+        assert_eq!(from_file.to_base64(), key.to_base64());
+
+        assert!(EncryptionKey::from_base64("short", "AAAA").is_err());
+    }
+}
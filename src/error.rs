@@ -14,9 +14,26 @@ pub enum ConfigError {
     #[error("Invalid file format: {0}")]
     InvalidFormat(String),
 
-    #[error("Validation error: {0}")]
+    /// A free-form message; only the "Validation error" prefix is localized,
+    /// use [`ConfigError::ValidationErrors`] with templated errors for the rest
+    #[error("{}", crate::i18n::message("validation_error", &[("message", .0.clone())]))]
     Validation(String),
 
+    #[error("{}", crate::i18n::message("validation_failed", &[("errors", join_errors(.0))]))]
+    ValidationErrors(Vec<crate::validator::ValidationError>),
+
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    #[error("Secret reference error: {0}")]
+    SecretReference(String),
+
+    #[error("Conversion failed: {}", join_errors(.0))]
+    Conversion(Vec<crate::convert::ConversionIssue>),
+
+    #[error("Migration error: {0}")]
+    Migration(String),
+
     #[error("Hot-reload error: {0}")]
     HotReload(String),
 
@@ -29,6 +46,9 @@ pub enum ConfigError {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("Configuration has no file path")]
+    NoFilePath,
+
     #[error("Timeout while loading configuration")]
     Timeout,
 
@@ -39,30 +59,69 @@ pub enum ConfigError {
 /// Result type for configuration operations
 pub type ConfigResult<T> = Result<T, ConfigError>;
 
+fn join_errors<E: ToString>(errors: &[E]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl From<serde_json::Error> for ConfigError {
     fn from(err: serde_json::Error) -> Self {
         ConfigError::Serde(format!("JSON: {err}"))
     }
 }
 
+#[cfg(feature = "yaml")]
 impl From<serde_yaml::Error> for ConfigError {
     fn from(err: serde_yaml::Error) -> Self {
         ConfigError::Serde(format!("YAML: {err}"))
     }
 }
 
+#[cfg(feature = "toml")]
 impl From<toml::de::Error> for ConfigError {
     fn from(err: toml::de::Error) -> Self {
         ConfigError::Serde(format!("TOML: {err}"))
     }
 }
 
+#[cfg(feature = "toml")]
 impl From<toml::ser::Error> for ConfigError {
     fn from(err: toml::ser::Error) -> Self {
         ConfigError::Serde(format!("TOML Serialization: {err}"))
     }
 }
 
+#[cfg(feature = "msgpack")]
+impl From<rmp_serde::decode::Error> for ConfigError {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        ConfigError::Serde(format!("MessagePack: {err}"))
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl From<rmp_serde::encode::Error> for ConfigError {
+    fn from(err: rmp_serde::encode::Error) -> Self {
+        ConfigError::Serde(format!("MessagePack Serialization: {err}"))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<ciborium::de::Error<std::io::Error>> for ConfigError {
+    fn from(err: ciborium::de::Error<std::io::Error>) -> Self {
+        ConfigError::Serde(format!("CBOR: {err}"))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<ciborium::ser::Error<std::io::Error>> for ConfigError {
+    fn from(err: ciborium::ser::Error<std::io::Error>) -> Self {
+        ConfigError::Serde(format!("CBOR Serialization: {err}"))
+    }
+}
+
 #[cfg(feature = "hot-reload")]
 impl From<notify::Error> for ConfigError {
     fn from(err: notify::Error) -> Self {
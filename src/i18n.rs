@@ -0,0 +1,355 @@
+//! Localized validation messages
+//!
+//! Validation errors carry their [`code`](crate::validator::ValidationError::code)
+//! and parameters, and their message is rendered from a template of the
+//! current [`MessageCatalog`]. English ([`EN`]) is the default; German
+//! ([`DE`]) ships with the crate and other languages plug in as catalogs:
+//!
+//! ```rust
+//! use rusty_config::i18n::{self, Catalog};
+//! use rusty_config::validator::TypeValidator;
+//!
+//! let error = TypeValidator::port(0, "server.port").unwrap_err();
+//! i18n::set_locale("de").unwrap();
+//! assert_eq!(
+//!     error.to_string(),
+//!     "Validierung fehlgeschlagen: server.port: muss ein gültiger Port zwischen 1 und 65535 sein (aktuell: 0) [port]"
+//! );
+//!
+//! i18n::set_catalog(Catalog::from(&i18n::DE).with("port", "ungültiger Port {actual}"));
+//! assert_eq!(
+//!     error.to_string(),
+//!     "Validierung fehlgeschlagen: server.port: ungültiger Port 0 [port]"
+//! );
+//! ```
+//!
+//! Templates refer to parameters as `{name}`. Errors whose code has several
+//! messages carry a `kind` parameter and are looked up as `code.kind` first,
+//! e.g. `permission_denied.write`. Errors without a template in the catalog
+//! keep their English message, as does the free-form text of
+//! [`ConfigError::Validation`], of which only the prefix is localized.
+
+use crate::{ConfigError, ConfigResult};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// A set of message templates for one locale
+pub trait MessageCatalog: Send + Sync {
+    /// Locale of the messages, e.g. `de`
+    fn locale(&self) -> &str;
+
+    /// Template for a message key, `None` if the catalog has none
+    fn template(&self, key: &str) -> Option<&str>;
+}
+
+/// A catalog compiled into the binary, see [`EN`] and [`DE`]
+#[derive(Debug, Clone, Copy)]
+pub struct StaticCatalog {
+    locale: &'static str,
+    templates: &'static [(&'static str, &'static str)],
+}
+
+impl MessageCatalog for StaticCatalog {
+    fn locale(&self) -> &str {
+        self.locale
+    }
+
+    fn template(&self, key: &str) -> Option<&str> {
+        self.templates
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, template)| *template)
+    }
+}
+
+/// A catalog built at runtime, e.g. to override single messages
+///
+/// ```rust
+/// use rusty_config::i18n::{Catalog, MessageCatalog, DE};
+///
+/// let catalog = Catalog::from(&DE).with("not_empty", "ist ein Pflichtfeld");
+/// assert_eq!(catalog.template("not_empty"), Some("ist ein Pflichtfeld"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    locale: String,
+    templates: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Create an empty catalog
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Add or replace the template for `key`
+    pub fn with(mut self, key: impl Into<String>, template: impl Into<String>) -> Self {
+        self.templates.insert(key.into(), template.into());
+        self
+    }
+}
+
+impl From<&StaticCatalog> for Catalog {
+    fn from(catalog: &StaticCatalog) -> Self {
+        Self {
+            locale: catalog.locale.to_string(),
+            templates: catalog
+                .templates
+                .iter()
+                .map(|(key, template)| (key.to_string(), template.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl MessageCatalog for Catalog {
+    fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn template(&self, key: &str) -> Option<&str> {
+        self.templates.get(key).map(String::as_str)
+    }
+}
+
+/// English messages
+#[rustfmt::skip]
+pub const EN: StaticCatalog = StaticCatalog {
+    locale: "en",
+    templates: &[
+        ("validation_failed", "Validation failed: {errors}"),
+        ("validation_error", "Validation error: {message}"),
+        ("not_empty", "must not be empty"),
+        ("length", "must be between {min} and {max} characters long (currently: {actual})"),
+        ("range", "must be between {min} and {max} (currently: {actual})"),
+        ("url", "must be a valid URL: {reason}"),
+        ("url_scheme", "URL scheme '{scheme}' is not allowed, expected one of {allowed}"),
+        ("email", "must be a valid email address"),
+        ("port", "must be a valid port between 1 and 65535 (currently: {actual})"),
+        ("hostname", "must be a valid hostname"),
+        ("ip", "must be a valid IP address"),
+        ("ipv4", "must be a valid IPv4 address"),
+        ("ipv6", "must be a valid IPv6 address"),
+        ("cidr", "must be a network in CIDR notation, e.g. 10.0.0.0/8"),
+        ("socket_addr", "must be a socket address like 127.0.0.1:8080"),
+        ("pattern", "must match the pattern '{pattern}'"),
+        ("semver.parse", "must be a semantic version: {reason}"),
+        ("semver.requirement", "version {version} does not satisfy '{requirement}'"),
+        ("semver_requirement", "must be a version requirement: {reason}"),
+        ("path_not_found", "'{path}' is not accessible: {reason}"),
+        ("not_a_file", "'{path}' is not a file"),
+        ("not_a_directory", "'{path}' is not a directory"),
+        ("not_a_socket", "'{path}' is not a Unix socket"),
+        ("permission_denied.read", "'{path}' is not readable"),
+        ("permission_denied.write", "'{path}' is not writable"),
+        ("permission_denied.execute", "'{path}' is not executable"),
+        ("one_of", "must be one of {allowed} (currently: {actual})"),
+        ("unique", "must not contain duplicates (duplicated: {duplicates})"),
+        ("duration", "must be between {min} and {max} (currently: {actual})"),
+        ("byte_size", "must be between {min} and {max} (currently: {actual})"),
+        ("percent", "must be between {min}% and {max}% (currently: {actual})"),
+        ("deprecated_key.renamed", "'{old}' is deprecated, use '{new}' instead"),
+        ("deprecated_key.ignored", "'{old}' is deprecated and ignored because '{new}' is set"),
+        ("deprecated_key.rewrite_failed", "Deprecated keys were not rewritten: {reason}"),
+        ("migration.rewrite_failed", "Migrated file was not written back: {reason}"),
+        ("unknown_key", "unknown key '{key}'"),
+        ("unknown_key.suggestion", "unknown key '{key}', did you mean '{suggestion}'?"),
+        ("timeout", "check timed out after {timeout}"),
+        ("port_unavailable", "cannot listen on {address}: {reason}"),
+        ("invalid_certificate.parse", "'{path}' is not a PEM certificate: {reason}"),
+        ("invalid_certificate.not_yet_valid", "certificate is not valid before {not_before}"),
+        ("certificate_expired.expired", "certificate expired on {not_after}"),
+        ("certificate_expired.expiring", "certificate expires on {not_after}, in less than {min_validity}"),
+        ("required", "is required"),
+        ("equals", "must be {expected}"),
+        ("any", "none of the alternatives hold: {failures}"),
+        ("requires", "is required when '{trigger}' is set"),
+        ("mutually_exclusive", "cannot be combined with '{other}'"),
+        ("exactly_one", "exactly one of {fields} is required"),
+        ("compare", "must be {operator} '{right}' (currently: {left_value} and {right_value})"),
+    ],
+};
+
+/// German messages
+#[rustfmt::skip]
+pub const DE: StaticCatalog = StaticCatalog {
+    locale: "de",
+    templates: &[
+        ("validation_failed", "Validierung fehlgeschlagen: {errors}"),
+        ("validation_error", "Validierungsfehler: {message}"),
+        ("not_empty", "darf nicht leer sein"),
+        ("length", "muss zwischen {min} und {max} Zeichen lang sein (aktuell: {actual})"),
+        ("range", "muss zwischen {min} und {max} liegen (aktuell: {actual})"),
+        ("url", "muss eine gültige URL sein: {reason}"),
+        ("url_scheme", "URL-Schema '{scheme}' ist nicht erlaubt, erwartet wird eines von {allowed}"),
+        ("email", "muss eine gültige E-Mail-Adresse sein"),
+        ("port", "muss ein gültiger Port zwischen 1 und 65535 sein (aktuell: {actual})"),
+        ("hostname", "muss ein gültiger Hostname sein"),
+        ("ip", "muss eine gültige IP-Adresse sein"),
+        ("ipv4", "muss eine gültige IPv4-Adresse sein"),
+        ("ipv6", "muss eine gültige IPv6-Adresse sein"),
+        ("cidr", "muss ein Netz in CIDR-Notation sein, z. B. 10.0.0.0/8"),
+        ("socket_addr", "muss eine Socket-Adresse wie 127.0.0.1:8080 sein"),
+        ("pattern", "muss dem Muster '{pattern}' entsprechen"),
+        ("semver.parse", "muss eine semantische Version sein: {reason}"),
+        ("semver.requirement", "Version {version} erfüllt '{requirement}' nicht"),
+        ("semver_requirement", "muss eine Versionsanforderung sein: {reason}"),
+        ("path_not_found", "'{path}' ist nicht zugänglich: {reason}"),
+        ("not_a_file", "'{path}' ist keine Datei"),
+        ("not_a_directory", "'{path}' ist kein Verzeichnis"),
+        ("not_a_socket", "'{path}' ist kein Unix-Socket"),
+        ("permission_denied.read", "'{path}' ist nicht lesbar"),
+        ("permission_denied.write", "'{path}' ist nicht beschreibbar"),
+        ("permission_denied.execute", "'{path}' ist nicht ausführbar"),
+        ("one_of", "muss einer der Werte {allowed} sein (aktuell: {actual})"),
+        ("unique", "darf keine Duplikate enthalten (doppelt: {duplicates})"),
+        ("duration", "muss zwischen {min} und {max} liegen (aktuell: {actual})"),
+        ("byte_size", "muss zwischen {min} und {max} liegen (aktuell: {actual})"),
+        ("percent", "muss zwischen {min} % und {max} % liegen (aktuell: {actual})"),
+        ("deprecated_key.renamed", "'{old}' ist veraltet, stattdessen '{new}' verwenden"),
+        ("deprecated_key.ignored", "'{old}' ist veraltet und wird ignoriert, weil '{new}' gesetzt ist"),
+        ("deprecated_key.rewrite_failed", "Veraltete Schlüssel wurden nicht umgeschrieben: {reason}"),
+        ("migration.rewrite_failed", "Migrierte Datei wurde nicht zurückgeschrieben: {reason}"),
+        ("unknown_key", "unbekannter Schlüssel '{key}'"),
+        ("unknown_key.suggestion", "unbekannter Schlüssel '{key}', meinten Sie '{suggestion}'?"),
+        ("timeout", "Prüfung nach {timeout} abgebrochen"),
+        ("port_unavailable", "kann nicht auf {address} lauschen: {reason}"),
+        ("invalid_certificate.parse", "'{path}' ist kein PEM-Zertifikat: {reason}"),
+        ("invalid_certificate.not_yet_valid", "Zertifikat ist erst ab {not_before} gültig"),
+        ("certificate_expired.expired", "Zertifikat ist am {not_after} abgelaufen"),
+        ("certificate_expired.expiring", "Zertifikat läuft am {not_after} ab, in weniger als {min_validity}"),
+        ("required", "ist erforderlich"),
+        ("equals", "muss {expected} sein"),
+        ("any", "keine der Alternativen ist erfüllt: {failures}"),
+        ("requires", "ist erforderlich, wenn '{trigger}' gesetzt ist"),
+        ("mutually_exclusive", "kann nicht mit '{other}' kombiniert werden"),
+        ("exactly_one", "genau eines von {fields} ist erforderlich"),
+        ("compare", "muss {operator} '{right}' sein (aktuell: {left_value} und {right_value})"),
+    ],
+};
+
+static CATALOG: RwLock<Option<Arc<dyn MessageCatalog>>> = RwLock::new(None);
+
+/// The catalog messages are rendered with, [`EN`] unless set otherwise
+pub fn catalog() -> Arc<dyn MessageCatalog> {
+    let current = CATALOG.read().unwrap_or_else(|e| e.into_inner()).clone();
+    current.unwrap_or_else(|| Arc::new(EN))
+}
+
+/// Render messages with `catalog` from now on
+pub fn set_catalog(catalog: impl MessageCatalog + 'static) {
+    *CATALOG.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(catalog));
+}
+
+/// Switch to a built-in catalog, e.g. `de`, `en-US` or `de_DE.UTF-8`
+pub fn set_locale(locale: &str) -> ConfigResult<()> {
+    let language = locale
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let catalog = [EN, DE]
+        .into_iter()
+        .find(|catalog| catalog.locale == language)
+        .ok_or_else(|| ConfigError::Unknown(format!("No message catalog for locale '{locale}'")))?;
+    set_catalog(catalog);
+    Ok(())
+}
+
+/// Template for `code`, or for `code.kind` if the parameters have a `kind`
+pub(crate) fn template<'a>(
+    catalog: &'a dyn MessageCatalog,
+    code: &str,
+    params: &BTreeMap<String, String>,
+) -> Option<&'a str> {
+    params
+        .get("kind")
+        .and_then(|kind| catalog.template(&format!("{code}.{kind}")))
+        .or_else(|| catalog.template(code))
+}
+
+/// Replace every `{name}` in `template` with its parameter
+///
+/// Unknown placeholders are kept as they are.
+pub fn render(template: &str, params: &BTreeMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder
+            .find('}')
+            .and_then(|end| Some((params.get(&placeholder[1..end])?, end)))
+        {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &placeholder[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Render a message of the current catalog, falling back to English
+pub(crate) fn message(key: &str, params: &[(&str, String)]) -> String {
+    let params: BTreeMap<String, String> = params
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+    let catalog = catalog();
+    let template = template(&*catalog, key, &params)
+        .or_else(|| template(&EN, key, &params))
+        .unwrap_or(key);
+    render(template, &params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_and_lookup() {
+        assert_eq!(
+            render(
+                "'{path}' is {what} {missing} {",
+                &params(&[("path", "/tmp"), ("what", "ok")])
+            ),
+            "'/tmp' is ok {missing} {"
+        );
+        let write = params(&[("kind", "write")]);
+        assert_eq!(
+            template(&DE, "permission_denied", &write),
+            Some("'{path}' ist nicht beschreibbar")
+        );
+        assert_eq!(
+            template(&DE, "ip", &write),
+            Some("muss eine gültige IP-Adresse sein")
+        );
+        assert_eq!(template(&DE, "maximum", &write), None);
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_keys() {
+        let keys = |catalog: &StaticCatalog| {
+            let mut keys: Vec<_> = catalog.templates.iter().map(|(key, _)| *key).collect();
+            keys.sort_unstable();
+            keys
+        };
+        assert_eq!(keys(&EN), keys(&DE));
+    }
+}
@@ -6,7 +6,7 @@
 //!
 //! - 🔄 **Hot-Reload**: Automatic reloading of configuration files
 //! - ✅ **Validation**: Robust validation of configuration data
-//! - 📁 **Multi-Format**: Support for YAML, JSON, and TOML
+//! - 📁 **Multi-Format**: Support for YAML, JSON, TOML, INI, Java properties, HCL, `.env`, MessagePack and CBOR
 //! - 🚀 **Async**: Fully asynchronous API
 //! - 🔒 **Type-Safe**: Strongly typed configurations
 //!
@@ -59,19 +59,38 @@
 //! ```
 
 pub mod builder;
+pub mod checks;
+pub mod cli;
 pub mod config;
+pub mod convert;
+#[cfg(feature = "dotenv")]
+pub mod dotenv;
+pub mod edit;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod error;
+pub mod i18n;
 pub mod loader;
+pub mod migration;
+pub mod resolver;
+pub mod rules;
+pub mod schema;
+pub mod secret;
+#[cfg(feature = "sops")]
+pub mod sops;
+pub mod units;
 pub mod validator;
+pub mod value;
 pub mod watcher;
 
 pub use builder::ConfigBuilder;
 pub use config::Config;
 pub use error::{ConfigError, ConfigResult};
+pub use secret::Secret;
 
 /// Re-export commonly used types
 pub mod prelude {
-    pub use crate::{Config, ConfigBuilder, ConfigError, ConfigResult};
+    pub use crate::{Config, ConfigBuilder, ConfigError, ConfigResult, Secret};
     pub use async_trait::async_trait;
     pub use serde::{Deserialize, Serialize};
 }
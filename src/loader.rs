@@ -1,9 +1,25 @@
+use crate::validator::{codes, ValidationError};
+use crate::value::{self, Value};
 use crate::{ConfigError, ConfigResult};
 use serde::{de::DeserializeOwned, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Load a config from a file
+///
+/// The format is taken from the file extension. Files without a known
+/// extension are detected from their content, see [`detect_content`].
 pub async fn load_from_file<T>(path: &Path) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    load_file(path, &LoadOptions::default()).await
+}
+
+/// Read and parse a file, decrypting it first if it is SOPS-encrypted
+async fn load_file<T>(
+    path: &Path,
+    #[allow(unused_variables)] options: &LoadOptions,
+) -> ConfigResult<T>
 where
     T: DeserializeOwned,
 {
@@ -13,52 +29,515 @@ where
         ));
     }
 
-    let content = tokio::fs::read_to_string(path).await?;
-    let extension = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    let bytes = tokio::fs::read(path).await?;
+    let format = detect_format(path).unwrap_or(ConfigFormat::Unknown);
+
+    #[cfg(feature = "sops")]
+    if let Some(tree) = crate::sops::decrypt_content(&bytes, &format, options.sops_keys.as_deref())?
+    {
+        return if format.is_flat() {
+            value::from_value_lenient(tree, "Config")
+        } else {
+            serde_json::from_value(tree).map_err(ConfigError::from)
+        };
+    }
+
+    load_from_bytes(bytes, &format)
+}
+
+/// Read a config file into a value tree, with the format it was read as
+async fn load_tree(
+    path: &Path,
+    #[allow(unused_variables)] options: &LoadOptions,
+) -> ConfigResult<(ConfigFormat, Value)> {
+    if !path.exists() {
+        return Err(ConfigError::FileNotFound(
+            path.to_string_lossy().to_string(),
+        ));
+    }
+
+    let bytes = tokio::fs::read(path).await?;
+    let format = detect_format(path).unwrap_or(ConfigFormat::Unknown);
+
+    #[cfg(feature = "sops")]
+    if let Some(tree) = crate::sops::decrypt_content(&bytes, &format, options.sops_keys.as_deref())?
+    {
+        return Ok((format, tree));
+    }
+
+    match format {
+        ConfigFormat::Unknown => detect_content(&bytes),
+        format => load_from_bytes(bytes, &format).map(|tree| (format, tree)),
+    }
+}
+
+/// How keys that no field of the config type reads are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownKeys {
+    /// Drop them silently, like serde does
+    #[default]
+    Ignore,
+    /// Report them as load warnings
+    Warn,
+    /// Fail the load with [`ConfigError::ValidationErrors`]
+    Deny,
+}
+
+/// Options applied to every load of a config, including hot-reloads
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Value tree the loaded document is deep-merged over before deserializing
+    pub defaults: Option<Value>,
+    /// Values set at dotted key paths after merging, with the highest precedence
+    pub overrides: Vec<(String, Value)>,
+    /// Keys for decrypting `ENC[...]` values, see [`crate::encryption`]
+    #[cfg(feature = "encryption")]
+    pub keyring: Option<std::sync::Arc<crate::encryption::Keyring>>,
+    /// Keys for SOPS-encrypted files, see [`crate::sops`]; taken from the
+    /// environment if not set
+    #[cfg(feature = "sops")]
+    pub sops_keys: Option<std::sync::Arc<crate::sops::SopsKeys>>,
+    /// JSON Schema the loaded document must satisfy before deserializing
+    #[cfg(feature = "schema-validation")]
+    pub schema: Option<std::sync::Arc<crate::schema::SchemaValidator>>,
+    /// Cross-field rules the loaded document must satisfy, see [`crate::rules`]
+    pub rules: Vec<crate::rules::Rule>,
+    /// Schema version migrations applied before anything else, see [`crate::migration`]
+    pub migrations: Option<crate::migration::Migrations>,
+    /// Write migrated files back, keeping a backup of the original
+    pub rewrite_migrated_file: bool,
+    /// Deprecated key paths and their replacements, as `(old, new)`
+    pub deprecated_keys: Vec<(String, String)>,
+    /// Write the file back with deprecated keys renamed
+    pub rewrite_deprecated_keys: bool,
+    /// Strict mode: report keys no field reads, with "did you mean" suggestions
+    pub unknown_keys: UnknownKeys,
+    /// Resolvers for secret references like `file://...`, see [`crate::resolver`]
+    pub resolvers: Vec<std::sync::Arc<dyn crate::resolver::SecretResolver>>,
+}
+
+impl LoadOptions {
+    /// Check whether loading needs to go through the value tree
+    fn needs_value_tree(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        if self.keyring.is_some() {
+            return true;
+        }
+        #[cfg(feature = "schema-validation")]
+        if self.schema.is_some() {
+            return true;
+        }
+        self.defaults.is_some()
+            || !self.overrides.is_empty()
+            || !self.resolvers.is_empty()
+            || !self.rules.is_empty()
+            || self.migrations.is_some()
+            || !self.deprecated_keys.is_empty()
+            || self.unknown_keys != UnknownKeys::Ignore
+    }
+
+    /// Check whether saving needs to look at the file's previous content
+    pub(crate) fn needs_previous_on_save(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        if self.keyring.is_some() {
+            return true;
+        }
+        !self.resolvers.is_empty()
+    }
+}
+
+/// Load a config from a file, applying the given load options
+pub async fn load_with_options<T>(path: &Path, options: &LoadOptions) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    load_with_sources(path, options)
+        .await
+        .map(|loaded| loaded.data)
+}
+
+/// A loaded config with what was found along the way
+pub(crate) struct Loaded<T> {
+    pub data: T,
+    /// Secret files the config references
+    pub sources: Vec<PathBuf>,
+    /// Warnings for the user, e.g. deprecated keys
+    pub warnings: Vec<ValidationError>,
+}
+
+/// Like [`load_with_options`], also returning secret files and warnings
+pub(crate) async fn load_with_sources<T>(
+    path: &Path,
+    options: &LoadOptions,
+) -> ConfigResult<Loaded<T>>
+where
+    T: DeserializeOwned,
+{
+    if !options.needs_value_tree() {
+        return Ok(Loaded {
+            data: load_file(path, options).await?,
+            sources: Vec::new(),
+            warnings: Vec::new(),
+        });
+    }
+
+    let (format, mut document) = load_tree(path, options).await?;
+    let mut warnings = Vec::new();
+    if let Some(migrations) = &options.migrations {
+        let report = migrations.apply(&mut document)?;
+        if report.is_migrated() && options.rewrite_migrated_file {
+            if let Err(e) = crate::migration::write_migrated(path, &document, report.from).await {
+                warnings.push(ValidationError::templated(
+                    "",
+                    codes::MIGRATION,
+                    &[
+                        ("kind", "rewrite_failed".to_string()),
+                        ("reason", e.to_string()),
+                    ],
+                ));
+            }
+        }
+    }
+    let renamed = rename_deprecated_keys(&mut document, &options.deprecated_keys);
+    let rewrite = options.rewrite_deprecated_keys && !renamed.is_empty();
+    warnings.extend(renamed);
+    if rewrite {
+        if let Err(e) = save_to_file(path, &document).await {
+            warnings.push(ValidationError::templated(
+                "",
+                codes::DEPRECATED_KEY,
+                &[
+                    ("kind", "rewrite_failed".to_string()),
+                    ("reason", e.to_string()),
+                ],
+            ));
+        }
+    }
+    #[cfg(feature = "logging")]
+    for warning in &warnings {
+        log::warn!("{}: {warning}", path.display());
+    }
+
+    let mut tree = options.defaults.clone().unwrap_or(Value::Null);
+    value::deep_merge(&mut tree, document);
+    for (path, override_value) in &options.overrides {
+        value::insert_path(&mut tree, path, override_value.clone());
+    }
+
+    #[cfg(feature = "encryption")]
+    if let Some(keyring) = &options.keyring {
+        crate::encryption::decrypt_tree(&mut tree, keyring)?;
+    }
+
+    // Flat formats carry every value as text; in typed formats only values
+    // from overrides and resolved secrets do
+    let mut coerced: Vec<String> = options
+        .overrides
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect();
+    coerced.extend(crate::resolver::reference_paths(&tree, &options.resolvers));
+    let sources = crate::resolver::resolve_tree(&mut tree, &options.resolvers).await?;
+
+    #[cfg(feature = "schema-validation")]
+    if let Some(schema) = &options.schema {
+        schema.check(&tree)?;
+    }
+
+    if !options.rules.is_empty() {
+        let mut ctx = crate::validator::ValidationContext::new();
+        for rule in &options.rules {
+            rule.check(&tree, &mut ctx);
+        }
+        ctx.finish()?;
+    }
+    let check_unknown = options.unknown_keys != UnknownKeys::Ignore;
+    let (data, unknown) = if format.is_flat() {
+        value::from_value_checked(tree, "Config")?
+    } else {
+        value::from_value_coercing(tree, "Config", coerced, check_unknown)?
+    };
+    if !check_unknown {
+        return Ok(Loaded {
+            data,
+            sources,
+            warnings,
+        });
+    }
 
-    match extension.as_str() {
-        "json" => load_json(&content),
-        "yaml" | "yml" => load_yaml(&content),
-        "toml" => load_toml(&content),
+    // `$schema` links the file to its JSON Schema, see `crate::schema`, and
+    // the schema version belongs to the migrations, not to the config type
+    let version_path = options.migrations.as_ref().map(|m| m.version_path());
+    let unknown = unknown
+        .into_iter()
+        .filter(|error| error.field != "$schema" && Some(error.field.as_str()) != version_path);
+    match options.unknown_keys {
+        UnknownKeys::Deny => {
+            let unknown: Vec<_> = unknown.collect();
+            if !unknown.is_empty() {
+                return Err(ConfigError::ValidationErrors(unknown));
+            }
+        }
         _ => {
-            // Try to auto-detect based on content
-            if content.trim().starts_with('{') || content.trim().starts_with('[') {
-                load_json(&content)
-            } else if content.trim().starts_with('#') || content.contains(':') {
-                load_yaml(&content)
+            for warning in unknown {
+                #[cfg(feature = "logging")]
+                log::warn!("{}: {warning}", path.display());
+                warnings.push(warning);
+            }
+        }
+    }
+    Ok(Loaded {
+        data,
+        sources,
+        warnings,
+    })
+}
+
+/// Move values from deprecated keys to their replacements
+///
+/// Returns a warning for every deprecated key found. If the replacement is
+/// set as well, the deprecated value is dropped.
+fn rename_deprecated_keys(
+    document: &mut Value,
+    deprecated_keys: &[(String, String)],
+) -> Vec<ValidationError> {
+    let mut warnings = Vec::new();
+    for (old, new) in deprecated_keys {
+        let Some(old_value) = value::remove_path(document, old) else {
+            continue;
+        };
+        let kind = if value::get_path(document, new).is_some() {
+            "ignored"
+        } else {
+            value::insert_path(document, new, old_value);
+            "renamed"
+        };
+        warnings.push(ValidationError::templated(
+            old,
+            codes::DEPRECATED_KEY,
+            &[
+                ("kind", kind.to_string()),
+                ("old", old.clone()),
+                ("new", new.clone()),
+            ],
+        ));
+    }
+    warnings
+}
+
+/// Load a config from raw content in the given format
+///
+/// With [`ConfigFormat::Unknown`] the format is detected from the content.
+pub fn load_from_bytes<T>(bytes: Vec<u8>, format: &ConfigFormat) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    match format {
+        ConfigFormat::Json => load_json(&into_text(bytes)?),
+        ConfigFormat::Yaml => load_yaml(&into_text(bytes)?),
+        ConfigFormat::Toml => load_toml(&into_text(bytes)?),
+        ConfigFormat::Ini => load_ini(&into_text(bytes)?),
+        ConfigFormat::Properties => load_properties(&into_text(bytes)?),
+        ConfigFormat::Hcl => load_hcl(&into_text(bytes)?),
+        ConfigFormat::Dotenv => load_dotenv(&into_text(bytes)?),
+        ConfigFormat::MessagePack => load_msgpack(&bytes),
+        ConfigFormat::Cbor => load_cbor(&bytes),
+        ConfigFormat::Unknown => {
+            // Reuse the tree parsed during detection; only flat formats need coercion
+            let (detected, tree) = detect_content(&bytes)?;
+            if detected.is_flat() {
+                value::from_value_lenient(tree, &format!("{detected:?}"))
             } else {
-                load_toml(&content)
+                serde_json::from_value(tree).map_err(ConfigError::from)
+            }
+        }
+    }
+}
+
+/// Decode file content for the text formats
+fn into_text(bytes: Vec<u8>) -> ConfigResult<String> {
+    String::from_utf8(bytes)
+        .map_err(|err| ConfigError::InvalidFormat(format!("File is not valid UTF-8: {err}")))
+}
+
+/// CBOR self-describe tag (55799) that marks a CBOR document
+const CBOR_MAGIC: [u8; 3] = [0xd9, 0xd9, 0xf7];
+
+/// Detect binary formats from their leading bytes
+///
+/// CBOR is recognized by its self-describe tag or a leading map header,
+/// MessagePack by a leading map header. Both map headers are invalid as
+/// the first byte of UTF-8 text, so text files are never matched.
+pub fn sniff_binary_format(bytes: &[u8]) -> Option<ConfigFormat> {
+    match bytes.first()? {
+        _ if bytes.starts_with(&CBOR_MAGIC) => Some(ConfigFormat::Cbor),
+        0x80..=0x8f | 0xde | 0xdf => Some(ConfigFormat::MessagePack),
+        0xa0..=0xbb | 0xbf => Some(ConfigFormat::Cbor),
+        _ => None,
+    }
+}
+
+/// Text formats tried by [`detect_content`], strictest first
+const TEXT_CANDIDATES: [ConfigFormat; 7] = [
+    ConfigFormat::Json,
+    ConfigFormat::Toml,
+    ConfigFormat::Yaml,
+    ConfigFormat::Hcl,
+    ConfigFormat::Ini,
+    ConfigFormat::Dotenv,
+    ConfigFormat::Properties,
+];
+
+/// Detect the format of content without relying on a file extension
+///
+/// Binary formats are recognized by their leading bytes. For text, every
+/// enabled parser is tried and each document that parses into a table or
+/// list is scored. Structured formats rank above the flat ones, which
+/// accept almost any line as `key=value`; within each group the parse
+/// that yields more values wins, and ties go to the stricter format. A
+/// TOML file with URLs also parses as a YAML string, but only TOML yields
+/// a table. Empty JSON and YAML collections (`{}`, `[]`, `---`) are valid
+/// documents, while flat formats need at least one key.
+///
+/// Returns the winning format together with the parsed value tree. If no
+/// parser accepts the content, the error lists every parser's failure.
+pub fn detect_content(bytes: &[u8]) -> ConfigResult<(ConfigFormat, Value)> {
+    if let Some(format) = sniff_binary_format(bytes) {
+        let value = load_from_bytes(bytes.to_vec(), &format)?;
+        return Ok((format, value));
+    }
+
+    let content = std::str::from_utf8(bytes)
+        .map_err(|err| ConfigError::InvalidFormat(format!("File is not valid UTF-8: {err}")))?;
+    if content.trim().is_empty() {
+        // An empty TOML document is a valid, empty table
+        return Ok((ConfigFormat::Toml, Value::Object(Default::default())));
+    }
+
+    let mut best: Option<((bool, usize), ConfigFormat, Value)> = None;
+    let mut failures = Vec::new();
+
+    for format in &TEXT_CANDIDATES {
+        let value = match load_from_bytes::<Value>(bytes.to_vec(), format) {
+            Ok(value) => value,
+            Err(ConfigError::FormatNotSupported(_)) => continue,
+            Err(err) => {
+                failures.push(format!("{format:?}: {err}"));
+                continue;
             }
+        };
+
+        let is_flat = format.is_flat();
+        let value = match value {
+            // A YAML stream without content, such as a lone `---`
+            Value::Null if *format == ConfigFormat::Yaml => Value::Object(Default::default()),
+            value => value,
+        };
+        let is_document = match &value {
+            Value::Object(table) => !is_flat || !table.is_empty(),
+            Value::Array(items) => !is_flat || !items.is_empty(),
+            _ => false,
+        };
+        if !is_document {
+            failures.push(format!("{format:?}: not a table or list"));
+            continue;
+        }
+
+        // Flat formats accept almost any line as `key=value`, so require plain keys
+        let entries = value::flatten(&value);
+        let has_plain_keys = entries.iter().all(|(path, _)| {
+            path.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                && path
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        });
+        if is_flat && !has_plain_keys {
+            failures.push(format!("{format:?}: keys contain unexpected characters"));
+            continue;
+        }
+
+        let score = (!is_flat, entries.len());
+        // Candidates are ordered strictest first, so only a higher score replaces the best
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _, _)| score > *best_score)
+        {
+            best = Some((score, format.clone(), value));
         }
     }
+
+    best.map(|(_, format, value)| (format, value))
+        .ok_or_else(|| {
+            ConfigError::InvalidFormat(format!(
+                "Could not detect config format: {}",
+                failures.join("; ")
+            ))
+        })
 }
 
 /// Save a config to a file
+///
+/// The format is taken from the file extension. For files without a known
+/// extension the format of the existing content is kept; new files are
+/// written as JSON.
 pub async fn save_to_file<T>(path: &Path, data: &T) -> ConfigResult<()>
 where
     T: Serialize,
 {
-    let extension = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    let content = match extension.as_str() {
-        "json" => save_json(data)?,
-        "yaml" | "yml" => save_yaml(data)?,
-        "toml" => save_toml(data)?,
-        _ => save_json(data)?, // Default is JSON
+    #[cfg(feature = "sops")]
+    if crate::sops::is_encrypted_file(path).await {
+        return Err(ConfigError::Encryption(format!(
+            "Refusing to overwrite SOPS-encrypted file '{}' with plain text",
+            path.display()
+        )));
+    }
+
+    let format = match detect_format(path) {
+        Some(ConfigFormat::Unknown) | None => {
+            existing_format(path).await.unwrap_or(ConfigFormat::Json)
+        }
+        Some(format) => format,
     };
 
+    let content = save_to_bytes(data, &format)?;
     tokio::fs::write(path, content).await?;
     Ok(())
 }
 
+/// Serialize a config into raw content in the given format
+///
+/// [`Secret`](crate::Secret) values are written in plain text.
+pub fn save_to_bytes<T>(data: &T, format: &ConfigFormat) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    crate::secret::expose_secrets(|| serialize_bytes(data, format))
+}
+
+fn serialize_bytes<T>(data: &T, format: &ConfigFormat) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    Ok(match format {
+        ConfigFormat::Json => save_json(data)?.into_bytes(),
+        ConfigFormat::Yaml => save_yaml(data)?.into_bytes(),
+        ConfigFormat::Toml => save_toml(data)?.into_bytes(),
+        ConfigFormat::Ini => save_ini(data)?.into_bytes(),
+        ConfigFormat::Properties => save_properties(data)?.into_bytes(),
+        ConfigFormat::Hcl => save_hcl(data)?.into_bytes(),
+        ConfigFormat::Dotenv => save_dotenv(data)?.into_bytes(),
+        ConfigFormat::MessagePack => save_msgpack(data)?,
+        ConfigFormat::Cbor => save_cbor(data)?,
+        ConfigFormat::Unknown => save_json(data)?.into_bytes(), // Default is JSON
+    })
+}
+
+/// Detect the format of an existing file from its content
+async fn existing_format(path: &Path) -> Option<ConfigFormat> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    detect_content(&bytes).ok().map(|(format, _)| format)
+}
+
 /// Load JSON config
 #[cfg(feature = "json")]
 fn load_json<T>(content: &str) -> ConfigResult<T>
@@ -161,14 +640,470 @@ where
     Err(ConfigError::FormatNotSupported("TOML".to_string()))
 }
 
+/// Load INI config
+///
+/// Sections and dotted keys are mapped into nested tables, so
+/// `[server.tls]` with `enabled = true` becomes `server.tls.enabled`.
+#[cfg(feature = "ini")]
+fn load_ini<T>(content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    let ini = ini::Ini::load_from_str(content)
+        .map_err(|err| ConfigError::Serde(format!("INI: {err}")))?;
+
+    let mut root = Value::Object(Default::default());
+    for (section, properties) in ini.iter() {
+        for (key, value) in properties.iter() {
+            let path = match section {
+                Some(section) => format!("{section}.{key}"),
+                None => key.to_string(),
+            };
+            value::insert_path(&mut root, &path, Value::String(value.to_string()));
+        }
+    }
+    value::normalize_arrays(&mut root);
+
+    value::from_value_lenient(root, "INI")
+}
+
+#[cfg(not(feature = "ini"))]
+fn load_ini<T>(_content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("INI".to_string()))
+}
+
+/// Save INI config
+///
+/// Top-level tables become sections, deeper nesting is flattened into dotted keys.
+#[cfg(feature = "ini")]
+fn save_ini<T>(data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    let root = to_table(data, "INI")?;
+    let mut ini = ini::Ini::new();
+
+    // The general section has to be written before any named section
+    for (key, child) in root.iter().filter(|(_, child)| !is_section(child)) {
+        let wrapped = Value::Object([(key.clone(), child.clone())].into_iter().collect());
+        for (path, leaf) in value::flatten(&wrapped) {
+            ini.with_general_section()
+                .set(path, value::scalar_to_string(&leaf));
+        }
+    }
+    for (section, child) in root.iter().filter(|(_, child)| is_section(child)) {
+        for (path, leaf) in value::flatten(child) {
+            ini.with_section(Some(section.as_str()))
+                .set(path, value::scalar_to_string(&leaf));
+        }
+    }
+
+    let mut buffer = Vec::new();
+    ini.write_to(&mut buffer)?;
+    String::from_utf8(buffer).map_err(|err| ConfigError::Serde(format!("INI: {err}")))
+}
+
+#[cfg(feature = "ini")]
+fn is_section(value: &Value) -> bool {
+    value.as_object().is_some_and(|table| !table.is_empty())
+}
+
+#[cfg(not(feature = "ini"))]
+fn save_ini<T>(_data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("INI".to_string()))
+}
+
+/// Load Java `.properties` config
+///
+/// Dotted keys are mapped into nested tables (`server.port=8080`).
+#[cfg(feature = "properties")]
+fn load_properties<T>(content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    let mut root = Value::Object(Default::default());
+    for (key, value) in properties::parse(content)? {
+        value::insert_path(&mut root, &key, Value::String(value));
+    }
+    value::normalize_arrays(&mut root);
+
+    value::from_value_lenient(root, "Properties")
+}
+
+#[cfg(not(feature = "properties"))]
+fn load_properties<T>(_content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("Properties".to_string()))
+}
+
+/// Save Java `.properties` config
+#[cfg(feature = "properties")]
+fn save_properties<T>(data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    let root = Value::Object(to_table(data, "Properties")?);
+    let mut content = String::new();
+    for (key, leaf) in value::flatten(&root) {
+        content.push_str(&properties::escape(&key, true));
+        content.push('=');
+        content.push_str(&properties::escape(&value::scalar_to_string(&leaf), false));
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+#[cfg(not(feature = "properties"))]
+fn save_properties<T>(_data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("Properties".to_string()))
+}
+
+/// Load dotenv config
+///
+/// Keys are mapped to nested paths, see [`crate::dotenv`].
+#[cfg(feature = "dotenv")]
+fn load_dotenv<T>(content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    value::from_value_lenient(crate::dotenv::to_tree(content)?, "dotenv")
+}
+
+#[cfg(not(feature = "dotenv"))]
+fn load_dotenv<T>(_content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("dotenv".to_string()))
+}
+
+/// Save dotenv config
+#[cfg(feature = "dotenv")]
+fn save_dotenv<T>(data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    let root = Value::Object(to_table(data, "dotenv")?);
+    Ok(crate::dotenv::from_tree(&root))
+}
+
+#[cfg(not(feature = "dotenv"))]
+fn save_dotenv<T>(_data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("dotenv".to_string()))
+}
+
+/// Load MessagePack config
+#[cfg(feature = "msgpack")]
+fn load_msgpack<T>(bytes: &[u8]) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    rmp_serde::from_slice(bytes).map_err(ConfigError::from)
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn load_msgpack<T>(_bytes: &[u8]) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("MessagePack".to_string()))
+}
+
+/// Save MessagePack config
+///
+/// Structs are encoded as maps with field names, so the file stays
+/// readable by other MessagePack tooling.
+#[cfg(feature = "msgpack")]
+fn save_msgpack<T>(data: &T) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    rmp_serde::to_vec_named(data).map_err(ConfigError::from)
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn save_msgpack<T>(_data: &T) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("MessagePack".to_string()))
+}
+
+/// Load CBOR config
+#[cfg(feature = "cbor")]
+fn load_cbor<T>(bytes: &[u8]) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    let bytes = bytes.strip_prefix(&CBOR_MAGIC).unwrap_or(bytes);
+    ciborium::from_reader(bytes).map_err(ConfigError::from)
+}
+
+#[cfg(not(feature = "cbor"))]
+fn load_cbor<T>(_bytes: &[u8]) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("CBOR".to_string()))
+}
+
+/// Save CBOR config, prefixed with the self-describe tag
+#[cfg(feature = "cbor")]
+fn save_cbor<T>(data: &T) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut bytes = CBOR_MAGIC.to_vec();
+    ciborium::into_writer(data, &mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(not(feature = "cbor"))]
+fn save_cbor<T>(_data: &T) -> ConfigResult<Vec<u8>>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("CBOR".to_string()))
+}
+
+/// Serialize data into a top-level table for the flat formats
+#[cfg(any(feature = "ini", feature = "properties", feature = "dotenv"))]
+fn to_table<T>(data: &T, format: &str) -> ConfigResult<serde_json::Map<String, Value>>
+where
+    T: Serialize,
+{
+    match serde_json::to_value(data)
+        .map_err(|err| ConfigError::Serde(format!("{format}: {err}")))?
+    {
+        Value::Object(table) => Ok(table),
+        _ => Err(ConfigError::InvalidFormat(format!(
+            "{format} requires a table at the top level"
+        ))),
+    }
+}
+
+/// Parser and writer for the Java `.properties` syntax
+#[cfg(feature = "properties")]
+mod properties {
+    use crate::{ConfigError, ConfigResult};
+
+    /// Parse properties content into key/value pairs in file order
+    pub fn parse(content: &str) -> ConfigResult<Vec<(String, String)>> {
+        let mut entries = Vec::new();
+        let mut lines = content.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                continue;
+            }
+
+            // Join continuation lines (odd number of trailing backslashes)
+            let mut logical = trimmed.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match lines.next() {
+                    Some((_, next)) => logical.push_str(next.trim_start()),
+                    None => break,
+                }
+            }
+
+            let (key, value) = split_key_value(&logical);
+            entries.push((
+                unescape(key).map_err(|err| invalid_line(number, err))?,
+                unescape(value).map_err(|err| invalid_line(number, err))?,
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    /// Escape a key or value for writing
+    pub fn escape(text: &str, is_key: bool) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for (index, c) in text.chars().enumerate() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '\u{c}' => escaped.push_str("\\f"),
+                '=' | ':' | '#' | '!' if is_key || index == 0 => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                ' ' if is_key || index == 0 => escaped.push_str("\\ "),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    fn ends_with_continuation(line: &str) -> bool {
+        line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+    }
+
+    fn split_key_value(line: &str) -> (&str, &str) {
+        let mut escaped = false;
+        let mut key_end = line.len();
+        for (index, c) in line.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '=' || c == ':' || c.is_whitespace() {
+                key_end = index;
+                break;
+            }
+        }
+
+        let key = &line[..key_end];
+        let rest = line[key_end..].trim_start();
+        let rest = rest
+            .strip_prefix('=')
+            .or_else(|| rest.strip_prefix(':'))
+            .unwrap_or(rest);
+        (key, rest.trim_start())
+    }
+
+    fn unescape(text: &str) -> Result<String, String> {
+        let mut result = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some('f') => result.push('\u{c}'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let code = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid unicode escape '\\u{hex}'"))?;
+                    result.push(code);
+                }
+                Some(other) => result.push(other),
+                None => {}
+            }
+        }
+        Ok(result)
+    }
+
+    fn invalid_line(number: usize, err: String) -> ConfigError {
+        ConfigError::InvalidFormat(format!("Properties line {}: {err}", number + 1))
+    }
+}
+
+/// Load HCL config
+///
+/// Blocks map to nested tables and block labels to nested keys, so
+/// `service "web" { port = 80 }` becomes `service.web.port`.
+#[cfg(feature = "hcl")]
+fn load_hcl<T>(content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    hcl::from_str(content).map_err(|err| ConfigError::Serde(format!("HCL: {err}")))
+}
+
+#[cfg(not(feature = "hcl"))]
+fn load_hcl<T>(_content: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    Err(ConfigError::FormatNotSupported("HCL".to_string()))
+}
+
+/// Save HCL config
+///
+/// Nested tables are written as blocks, everything else as attributes.
+#[cfg(feature = "hcl")]
+fn save_hcl<T>(data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    let body = match serde_json::to_value(data)? {
+        serde_json::Value::Object(table) => hcl_body(&table)?,
+        _ => {
+            return Err(ConfigError::InvalidFormat(
+                "HCL requires a table at the top level".to_string(),
+            ))
+        }
+    };
+    hcl::to_string(&body).map_err(|err| ConfigError::Serde(format!("HCL: {err}")))
+}
+
+#[cfg(not(feature = "hcl"))]
+fn save_hcl<T>(_data: &T) -> ConfigResult<String>
+where
+    T: Serialize,
+{
+    Err(ConfigError::FormatNotSupported("HCL".to_string()))
+}
+
+/// Build an HCL body from a table, using blocks for nested tables
+#[cfg(feature = "hcl")]
+fn hcl_body(table: &serde_json::Map<String, serde_json::Value>) -> ConfigResult<hcl::Body> {
+    let mut body = hcl::Body::builder();
+    for (key, value) in table {
+        let identifier = hcl::Identifier::new(key.as_str()).ok();
+        body = match (value, identifier) {
+            (serde_json::Value::Object(child), Some(identifier)) if !child.is_empty() => body
+                .add_block(
+                    hcl::Block::builder(identifier)
+                        .add_structures(hcl_body(child)?)
+                        .build(),
+                ),
+            _ => {
+                let expression = hcl::to_expression(value)
+                    .map_err(|err| ConfigError::Serde(format!("HCL: {err}")))?;
+                body.add_attribute(hcl::Attribute::new(key.as_str(), expression))
+            }
+        };
+    }
+    Ok(body.build())
+}
+
 /// Detect the format of a file based on its extension
+///
+/// Dotenv files are recognized by name (`.env`, `.env.local`, `app.env`).
 pub fn detect_format(path: &Path) -> Option<ConfigFormat> {
+    let file_name = path.file_name().and_then(|name| name.to_str())?;
+    if file_name == ".env" || file_name.starts_with(".env.") {
+        return Some(ConfigFormat::Dotenv);
+    }
+
     path.extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| match ext.to_lowercase().as_str() {
             "json" => ConfigFormat::Json,
             "yaml" | "yml" => ConfigFormat::Yaml,
             "toml" => ConfigFormat::Toml,
+            "ini" => ConfigFormat::Ini,
+            "properties" => ConfigFormat::Properties,
+            "hcl" => ConfigFormat::Hcl,
+            "env" => ConfigFormat::Dotenv,
+            "msgpack" | "mpk" => ConfigFormat::MessagePack,
+            "cbor" => ConfigFormat::Cbor,
             _ => ConfigFormat::Unknown,
         })
 }
@@ -179,6 +1114,12 @@ pub enum ConfigFormat {
     Json,
     Yaml,
     Toml,
+    Ini,
+    Properties,
+    Hcl,
+    Dotenv,
+    MessagePack,
+    Cbor,
     Unknown,
 }
 
@@ -189,21 +1130,60 @@ impl ConfigFormat {
             ConfigFormat::Json => "json",
             ConfigFormat::Yaml => "yaml",
             ConfigFormat::Toml => "toml",
+            ConfigFormat::Ini => "ini",
+            ConfigFormat::Properties => "properties",
+            ConfigFormat::Hcl => "hcl",
+            ConfigFormat::Dotenv => "env",
+            ConfigFormat::MessagePack => "msgpack",
+            ConfigFormat::Cbor => "cbor",
             ConfigFormat::Unknown => "json",
         }
     }
 
+    /// Check whether the format is a binary encoding
+    pub fn is_binary(&self) -> bool {
+        matches!(self, ConfigFormat::MessagePack | ConfigFormat::Cbor)
+    }
+
+    /// Check whether the format stores every value as text
+    pub fn is_flat(&self) -> bool {
+        matches!(
+            self,
+            ConfigFormat::Ini | ConfigFormat::Properties | ConfigFormat::Dotenv
+        )
+    }
+
     /// Get the MIME type for the format
     pub fn mime_type(&self) -> &'static str {
         match self {
             ConfigFormat::Json => "application/json",
             ConfigFormat::Yaml => "application/x-yaml",
             ConfigFormat::Toml => "application/toml",
+            ConfigFormat::Ini => "text/plain",
+            ConfigFormat::Properties => "text/x-java-properties",
+            ConfigFormat::Hcl => "application/hcl",
+            ConfigFormat::Dotenv => "text/plain",
+            ConfigFormat::MessagePack => "application/msgpack",
+            ConfigFormat::Cbor => "application/cbor",
             ConfigFormat::Unknown => "application/json",
         }
     }
 }
 
+impl std::str::FromStr for ConfigFormat {
+    type Err = ConfigError;
+
+    /// Parse a format name or file extension, e.g. `yaml`, `yml` or `env`
+    fn from_str(name: &str) -> ConfigResult<Self> {
+        match detect_format(Path::new(&format!("config.{name}"))) {
+            Some(ConfigFormat::Unknown) | None => Err(ConfigError::FormatNotSupported(format!(
+                "Unknown format '{name}'"
+            ))),
+            Some(format) => Ok(format),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,6 +1262,366 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_load_save_ini() {
+        #[cfg(feature = "ini")]
+        {
+            let config = TestConfig {
+                name: "test".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let path = temp_file.path().with_extension("ini");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_save_properties() {
+        #[cfg(feature = "properties")]
+        {
+            let config = TestConfig {
+                name: "a = b: c".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let path = temp_file.path().with_extension("properties");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_save_hcl() {
+        #[cfg(feature = "hcl")]
+        {
+            let config = TestConfig {
+                name: "test".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let path = temp_file.path().with_extension("hcl");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_save_dotenv() {
+        #[cfg(feature = "dotenv")]
+        {
+            let config = TestConfig {
+                name: "test value".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_dir = tempfile::tempdir().unwrap();
+            let path = temp_dir.path().join(".env");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+            let content = tokio::fs::read_to_string(&path).await.unwrap();
+            assert!(content.contains("NAME=\"test value\""));
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_save_msgpack() {
+        #[cfg(feature = "msgpack")]
+        {
+            let config = TestConfig {
+                name: "test".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let path = temp_file.path().with_extension("msgpack");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_save_cbor() {
+        #[cfg(feature = "cbor")]
+        {
+            let config = TestConfig {
+                name: "test".to_string(),
+                value: 42,
+                enabled: true,
+            };
+
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let path = temp_file.path().with_extension("cbor");
+
+            // Save
+            save_to_file(&path, &config).await.unwrap();
+
+            // Load
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    async fn test_sniff_binary_without_extension() {
+        let config = TestConfig {
+            name: "edge".to_string(),
+            value: 7,
+            enabled: false,
+        };
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        for (format, bytes) in [
+            (ConfigFormat::MessagePack, save_msgpack(&config).unwrap()),
+            (ConfigFormat::Cbor, save_cbor(&config).unwrap()),
+        ] {
+            assert_eq!(sniff_binary_format(&bytes), Some(format));
+
+            let path = temp_dir.path().join("bundle");
+            tokio::fs::write(&path, bytes).await.unwrap();
+            let loaded: TestConfig = load_from_file(&path).await.unwrap();
+            assert_eq!(config, loaded);
+        }
+
+        assert_eq!(sniff_binary_format(b"{\"name\": 1}"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "hcl")]
+    fn test_hcl_blocks_and_labels() {
+        let content = r#"
+            name = "edge"
+
+            service "web" {
+              port = 80
+              tls {
+                enabled = true
+              }
+            }
+        "#;
+
+        let loaded: serde_json::Value = load_hcl(content).unwrap();
+        assert_eq!(
+            loaded,
+            serde_json::json!({
+                "name": "edge",
+                "service": {"web": {"port": 80, "tls": {"enabled": true}}}
+            })
+        );
+
+        // Nested tables are written back as blocks and read into the same tree
+        let saved = save_hcl(&loaded).unwrap();
+        assert!(saved.contains("service {"));
+        let reloaded: serde_json::Value = load_hcl(&saved).unwrap();
+        assert_eq!(loaded, reloaded);
+    }
+
+    #[cfg(any(feature = "ini", feature = "properties"))]
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct NestedConfig {
+        name: String,
+        server: ServerSection,
+    }
+
+    #[cfg(any(feature = "ini", feature = "properties"))]
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ServerSection {
+        port: u16,
+        tls: TlsSection,
+        hosts: Vec<String>,
+    }
+
+    #[cfg(any(feature = "ini", feature = "properties"))]
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TlsSection {
+        enabled: bool,
+    }
+
+    #[cfg(any(feature = "ini", feature = "properties"))]
+    fn expected_nested() -> NestedConfig {
+        NestedConfig {
+            name: "legacy".to_string(),
+            server: ServerSection {
+                port: 8080,
+                tls: TlsSection { enabled: true },
+                hosts: vec!["a".to_string(), "b".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ini")]
+    fn test_ini_sections_to_nested() {
+        let content = "name = legacy\n\n[server]\nport = 8080\nhosts.0 = a\nhosts.1 = b\n\n[server.tls]\nenabled = true\n";
+        let loaded: NestedConfig = load_ini(content).unwrap();
+        assert_eq!(loaded, expected_nested());
+    }
+
+    #[test]
+    #[cfg(feature = "properties")]
+    fn test_properties_dotted_keys_to_nested() {
+        let content = "# legacy file\nname legacy\nserver.port : 8080\nserver.tls.enabled=true\n\
+                       server.hosts.0=a\nserver.hosts.1=\\\n    b\n";
+        let loaded: NestedConfig = load_properties(content).unwrap();
+        assert_eq!(loaded, expected_nested());
+    }
+
+    #[tokio::test]
+    async fn test_load_partial_file_over_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.yaml");
+        tokio::fs::write(&path, "value: 7\n").await.unwrap();
+
+        let defaults = TestConfig {
+            name: "default".to_string(),
+            value: 1,
+            enabled: true,
+        };
+        // `keyring` only exists with the `encryption` feature
+        #[allow(clippy::needless_update)]
+        let options = LoadOptions {
+            defaults: Some(serde_json::to_value(&defaults).unwrap()),
+            overrides: vec![("name".to_string(), Value::String("cli".to_string()))],
+            ..LoadOptions::default()
+        };
+
+        assert!(load_from_file::<TestConfig>(&path).await.is_err());
+        let loaded: TestConfig = load_with_options(&path, &options).await.unwrap();
+        assert_eq!(
+            loaded,
+            TestConfig {
+                name: "cli".to_string(),
+                value: 7,
+                ..defaults
+            }
+        );
+    }
+
+    #[test]
+    fn test_detect_content() {
+        let toml_with_urls = "url = \"https://example.com:8443/path\"\nport = 8080\n";
+        let (format, value) = detect_content(toml_with_urls.as_bytes()).unwrap();
+        assert_eq!(format, ConfigFormat::Toml);
+        assert_eq!(value["port"], 8080);
+
+        let yaml = "# comment\nserver:\n  port: 8080\n";
+        assert_eq!(
+            detect_content(yaml.as_bytes()).unwrap().0,
+            ConfigFormat::Yaml
+        );
+
+        let json = "{\"server\": {\"port\": 8080}}";
+        assert_eq!(
+            detect_content(json.as_bytes()).unwrap().0,
+            ConfigFormat::Json
+        );
+
+        #[cfg(feature = "ini")]
+        {
+            let ini = "[server]\nhost = localhost\n";
+            assert_eq!(detect_content(ini.as_bytes()).unwrap().0, ConfigFormat::Ini);
+        }
+    }
+
+    #[test]
+    fn test_detect_empty_documents() {
+        let (format, value) = detect_content(b"{}").unwrap();
+        assert_eq!((format, value), (ConfigFormat::Json, serde_json::json!({})));
+        let (format, value) = detect_content(b"[]\n").unwrap();
+        assert_eq!((format, value), (ConfigFormat::Json, serde_json::json!([])));
+
+        #[cfg(feature = "yaml")]
+        {
+            let (format, value) = detect_content(b"---\n").unwrap();
+            assert_eq!((format, value), (ConfigFormat::Yaml, serde_json::json!({})));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_created_extensionless_file_loads_again() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config");
+        tokio::fs::write(&path, "{}").await.unwrap();
+
+        let value: Value = load_from_file(&path).await.unwrap();
+        assert_eq!(value, serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_load_from_bytes_detects_format() {
+        let toml = b"name = \"test\"\nvalue = 1\nenabled = true\n".to_vec();
+        let config: TestConfig = load_from_bytes(toml, &ConfigFormat::Unknown).unwrap();
+        assert_eq!((config.name.as_str(), config.value), ("test", 1));
+
+        #[cfg(feature = "properties")]
+        {
+            let properties = b"name=test\nvalue=2\nenabled=false\n".to_vec();
+            let config: TestConfig = load_from_bytes(properties, &ConfigFormat::Unknown).unwrap();
+            assert_eq!((config.value, config.enabled), (2, false));
+        }
+    }
+
+    #[test]
+    fn test_detect_content_reports_all_errors() {
+        let err = detect_content(b"{ not: [valid").unwrap_err().to_string();
+        assert!(err.contains("Json:"));
+        assert!(err.contains("Toml:"));
+        assert!(err.contains("Yaml:"));
+    }
+
+    #[tokio::test]
+    async fn test_save_keeps_detected_format() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config");
+        tokio::fs::write(&path, "name = \"test\"\nvalue = 1\nenabled = false\n")
+            .await
+            .unwrap();
+
+        let mut config: TestConfig = load_from_file(&path).await.unwrap();
+        config.value = 2;
+        save_to_file(&path, &config).await.unwrap();
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(content.contains("value = 2"));
+        let (format, _) = detect_content(content.as_bytes()).unwrap();
+        assert_eq!(format, ConfigFormat::Toml);
+    }
+
     #[test]
     fn test_detect_format() {
         assert_eq!(
@@ -300,9 +1640,35 @@ mod tests {
             detect_format(Path::new("config.toml")),
             Some(ConfigFormat::Toml)
         );
+        assert_eq!(
+            detect_format(Path::new("config.ini")),
+            Some(ConfigFormat::Ini)
+        );
+        assert_eq!(
+            detect_format(Path::new("config.properties")),
+            Some(ConfigFormat::Properties)
+        );
+        assert_eq!(
+            detect_format(Path::new("config.hcl")),
+            Some(ConfigFormat::Hcl)
+        );
+        assert_eq!(
+            detect_format(Path::new(".env.local")),
+            Some(ConfigFormat::Dotenv)
+        );
+        assert_eq!(
+            detect_format(Path::new("bundle.msgpack")),
+            Some(ConfigFormat::MessagePack)
+        );
+        assert_eq!(
+            detect_format(Path::new("bundle.cbor")),
+            Some(ConfigFormat::Cbor)
+        );
         assert_eq!(
             detect_format(Path::new("config.txt")),
             Some(ConfigFormat::Unknown)
         );
+        assert_eq!("YML".parse::<ConfigFormat>().unwrap(), ConfigFormat::Yaml);
+        assert!("txt".parse::<ConfigFormat>().is_err());
     }
 }
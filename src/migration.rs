@@ -0,0 +1,471 @@
+//! Migrations between config schema versions
+//!
+//! A config file records its layout version in a `schema_version` key.
+//! Registered migrations upgrade older files step by step on the value
+//! tree, before it is deserialized:
+//!
+//! ```rust
+//! use rusty_config::migration::{Migration, Migrations};
+//! use serde_json::json;
+//!
+//! let migrations = Migrations::new()
+//!     .migration(Migration::new(1, 2).rename("server.addr", "host"))
+//!     .migration(
+//!         Migration::new(2, 3)
+//!             .move_subtree("server.tls", "tls")
+//!             .transform("timeout", |timeout| Ok(json!(format!("{}s", timeout)))),
+//!     );
+//!
+//! let mut tree = json!({
+//!     "server": { "addr": "0.0.0.0", "tls": { "enabled": true } },
+//!     "timeout": 30,
+//! });
+//! let report = migrations.apply(&mut tree).unwrap();
+//! assert_eq!((report.from, report.to), (1, 3));
+//! assert_eq!(
+//!     tree,
+//!     json!({
+//!         "server": { "host": "0.0.0.0" },
+//!         "timeout": "30s",
+//!         "tls": { "enabled": true },
+//!         "schema_version": 3,
+//!     })
+//! );
+//! ```
+//!
+//! Attached with [`ConfigBuilder::migrations`](crate::ConfigBuilder::migrations),
+//! files are migrated on every load and can be written back with
+//! [`ConfigBuilder::rewrite_migrated_file`](crate::ConfigBuilder::rewrite_migrated_file).
+//! [`Migrations::dry_run`] shows what a migration would change without
+//! touching the file.
+
+use crate::value::{self, Change, Value};
+use crate::{ConfigError, ConfigResult};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type TransformFn = dyn Fn(Value) -> ConfigResult<Value> + Send + Sync;
+type CustomFn = dyn Fn(&mut Value) -> ConfigResult<()> + Send + Sync;
+
+/// One step of a [`Migration`]
+#[derive(Clone)]
+enum Step {
+    Rename {
+        path: String,
+        name: String,
+    },
+    Move {
+        from: String,
+        to: String,
+    },
+    Transform {
+        path: String,
+        transform: Arc<TransformFn>,
+    },
+    Custom(Arc<CustomFn>),
+}
+
+impl Step {
+    fn apply(&self, tree: &mut Value) -> ConfigResult<()> {
+        match self {
+            Step::Rename { path, name } => {
+                let (parent_path, old) = path.rsplit_once('.').unwrap_or(("", path));
+                if let Some(Value::Object(table)) = value::get_path_mut(tree, parent_path) {
+                    if table.contains_key(old) {
+                        // Rebuild the table to keep the key in its place
+                        *table = std::mem::take(table)
+                            .into_iter()
+                            .map(|(key, child)| {
+                                if key == *old {
+                                    (name.clone(), child)
+                                } else {
+                                    (key, child)
+                                }
+                            })
+                            .collect();
+                    }
+                }
+            }
+            Step::Move { from, to } => {
+                if let Some(subtree) = value::remove_path(tree, from) {
+                    value::insert_path(tree, to, subtree);
+                }
+            }
+            Step::Transform { path, transform } => {
+                if let Some(target) = value::get_path_mut(tree, path) {
+                    *target = transform(std::mem::take(target))
+                        .map_err(|e| ConfigError::Migration(format!("'{path}': {e}")))?;
+                }
+            }
+            Step::Custom(migrate) => migrate(tree)?,
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Rename { path, name } => write!(f, "rename({path} -> {name})"),
+            Step::Move { from, to } => write!(f, "move({from} -> {to})"),
+            Step::Transform { path, .. } => write!(f, "transform({path})"),
+            Step::Custom(_) => write!(f, "custom"),
+        }
+    }
+}
+
+/// Steps that upgrade a config from one schema version to the next
+///
+/// Steps run in the order they were added. Steps whose source path is
+/// missing in the file are skipped.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    from: u64,
+    to: u64,
+    steps: Vec<Step>,
+}
+
+impl Migration {
+    /// Create a migration from version `from` to version `to`
+    pub fn new(from: u64, to: u64) -> Self {
+        Self {
+            from,
+            to,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Rename the last key of the dotted `path` to `name`, keeping its place
+    pub fn rename(mut self, path: impl Into<String>, name: impl Into<String>) -> Self {
+        self.steps.push(Step::Rename {
+            path: path.into(),
+            name: name.into(),
+        });
+        self
+    }
+
+    /// Move the value at `from` with everything below it to `to`
+    ///
+    /// A value already at `to` is replaced.
+    pub fn move_subtree(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.steps.push(Step::Move {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Replace the value at `path` with the result of `transform`
+    pub fn transform<F>(mut self, path: impl Into<String>, transform: F) -> Self
+    where
+        F: Fn(Value) -> ConfigResult<Value> + Send + Sync + 'static,
+    {
+        self.steps.push(Step::Transform {
+            path: path.into(),
+            transform: Arc::new(transform),
+        });
+        self
+    }
+
+    /// Change the whole tree, for anything the other steps cannot express
+    pub fn custom<F>(mut self, migrate: F) -> Self
+    where
+        F: Fn(&mut Value) -> ConfigResult<()> + Send + Sync + 'static,
+    {
+        self.steps.push(Step::Custom(Arc::new(migrate)));
+        self
+    }
+
+    /// Version this migration upgrades from
+    pub fn from_version(&self) -> u64 {
+        self.from
+    }
+
+    /// Version this migration upgrades to
+    pub fn to_version(&self) -> u64 {
+        self.to
+    }
+
+    fn apply(&self, tree: &mut Value) -> ConfigResult<()> {
+        for step in &self.steps {
+            step.apply(tree).map_err(|e| match e {
+                ConfigError::Migration(message) => {
+                    ConfigError::Migration(format!("{} -> {}: {message}", self.from, self.to))
+                }
+                other => other,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// What a migration did or would do, see [`Migrations::apply`]
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    /// Schema version of the file
+    pub from: u64,
+    /// Schema version after migrating
+    pub to: u64,
+    /// Changes to the value tree, including the version key
+    pub changes: Vec<Change>,
+    /// Copy of the file before it was rewritten
+    pub backup: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    /// Whether any migration ran
+    pub fn is_migrated(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_migrated() {
+            return write!(f, "schema version {} is up to date", self.from);
+        }
+        write!(f, "schema version {} -> {}", self.from, self.to)?;
+        for change in &self.changes {
+            write!(f, "\n{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The registered migrations of a config
+#[derive(Debug, Clone)]
+pub struct Migrations {
+    version_key: String,
+    initial_version: u64,
+    migrations: Vec<Migration>,
+}
+
+impl Migrations {
+    /// Create an empty chain using the `schema_version` key, starting at 1
+    pub fn new() -> Self {
+        Self {
+            version_key: "schema_version".to_string(),
+            initial_version: 1,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Dotted key path that holds the schema version
+    pub fn version_key(mut self, key: impl Into<String>) -> Self {
+        self.version_key = key.into();
+        self
+    }
+
+    /// Version assumed for files without the version key
+    pub fn initial_version(mut self, version: u64) -> Self {
+        self.initial_version = version;
+        self
+    }
+
+    /// Register a migration
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    /// Dotted key path that holds the schema version
+    pub(crate) fn version_path(&self) -> &str {
+        &self.version_key
+    }
+
+    /// The version files are migrated to
+    pub fn latest(&self) -> u64 {
+        self.migrations
+            .iter()
+            .map(Migration::to_version)
+            .fold(self.initial_version, u64::max)
+    }
+
+    /// Schema version of a value tree
+    ///
+    /// Accepts a number or a numeric string.
+    pub fn version_of(&self, tree: &Value) -> ConfigResult<u64> {
+        let version = match value::get_path(tree, &self.version_key) {
+            None | Some(Value::Null) => return Ok(self.initial_version),
+            Some(version) => version,
+        };
+        let parsed = match version {
+            Value::Number(number) => number.as_u64(),
+            Value::String(text) => text.parse().ok(),
+            _ => None,
+        };
+        parsed.ok_or_else(|| {
+            ConfigError::Migration(format!(
+                "'{}' must be a version number, found {version}",
+                self.version_key
+            ))
+        })
+    }
+
+    /// Set the version key to [`latest`](Self::latest) unless it is set
+    pub fn stamp(&self, tree: &mut Value) {
+        if matches!(
+            value::get_path(tree, &self.version_key),
+            None | Some(Value::Null)
+        ) {
+            value::insert_path(tree, &self.version_key, Value::from(self.latest()));
+        }
+    }
+
+    /// Migrate a value tree to the latest version
+    ///
+    /// Fails if the tree is newer than the latest version or no migration
+    /// continues the chain from its version.
+    pub fn apply(&self, tree: &mut Value) -> ConfigResult<MigrationReport> {
+        let from = self.version_of(tree)?;
+        let latest = self.latest();
+        if from > latest {
+            return Err(ConfigError::Migration(format!(
+                "schema version {from} is newer than the latest supported version {latest}"
+            )));
+        }
+
+        let before = (from < latest).then(|| tree.clone());
+        let mut version = from;
+        while version < latest {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|migration| migration.from == version && migration.to > version)
+                .ok_or_else(|| {
+                    ConfigError::Migration(format!("no migration from schema version {version}"))
+                })?;
+            migration.apply(tree)?;
+            version = migration.to;
+        }
+        let changes = match before {
+            Some(before) => {
+                value::insert_path(tree, &self.version_key, Value::from(version));
+                value::diff(&before, tree)
+            }
+            None => Vec::new(),
+        };
+
+        Ok(MigrationReport {
+            from,
+            to: version,
+            changes,
+            backup: None,
+        })
+    }
+
+    /// Show what migrating a file would change, without writing it
+    pub async fn dry_run(&self, path: impl AsRef<Path>) -> ConfigResult<MigrationReport> {
+        let mut tree: Value = crate::loader::load_from_file(path.as_ref()).await?;
+        self.apply(&mut tree)
+    }
+
+    /// Migrate a file in place, keeping a copy of the original
+    ///
+    /// The copy is written next to the file as `<name>.v<version>.bak`. The
+    /// file is rewritten from its parsed content, so comments are lost.
+    pub async fn migrate_file(&self, path: impl AsRef<Path>) -> ConfigResult<MigrationReport> {
+        let path = path.as_ref();
+        let mut tree: Value = crate::loader::load_from_file(path).await?;
+        let mut report = self.apply(&mut tree)?;
+        if report.is_migrated() {
+            report.backup = Some(write_migrated(path, &tree, report.from).await?);
+        }
+        Ok(report)
+    }
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Back up `path` and write the migrated tree in its place
+pub(crate) async fn write_migrated(path: &Path, tree: &Value, from: u64) -> ConfigResult<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!("{file_name}.v{from}.bak"));
+    tokio::fs::copy(path, &backup).await?;
+    crate::loader::save_to_file(path, tree).await?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .migration(Migration::new(1, 2).rename("db.url", "dsn"))
+            .migration(
+                Migration::new(2, 3)
+                    .move_subtree("db", "database")
+                    .transform("database.pool", |pool| match pool.as_u64() {
+                        Some(size) => Ok(json!({ "max": size })),
+                        None => Err(ConfigError::Validation("pool must be a number".into())),
+                    }),
+            )
+    }
+
+    #[test]
+    fn test_apply_chain() {
+        let mut tree = json!({"name": "app", "db": {"url": "pg://", "pool": 5}});
+        let report = migrations().apply(&mut tree).unwrap();
+        assert_eq!(
+            tree,
+            json!({
+                "name": "app",
+                "database": {"dsn": "pg://", "pool": {"max": 5}},
+                "schema_version": 3,
+            })
+        );
+        assert_eq!(report.changes.len(), 3);
+        assert_eq!(
+            report.changes[0].to_string(),
+            r#"- db = {"url":"pg://","pool":5}"#
+        );
+
+        // Up to date trees are left alone
+        let report = migrations().apply(&mut tree).unwrap();
+        assert!(!report.is_migrated());
+        assert!(report.changes.is_empty());
+
+        let mut tree = json!({"schema_version": "2", "db": {"pool": "big"}});
+        let err = migrations().apply(&mut tree).unwrap_err().to_string();
+        assert!(err.contains("2 -> 3: 'database.pool'"), "{err}");
+
+        let mut tree = json!({"schema_version": 4});
+        assert!(matches!(
+            migrations().apply(&mut tree),
+            Err(ConfigError::Migration(_))
+        ));
+        let gap = Migrations::new().migration(Migration::new(2, 3));
+        assert!(gap.apply(&mut json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_and_migrate_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("app.yaml");
+        let original = "db:\n  url: pg://\n  pool: 5\n";
+        tokio::fs::write(&path, original).await.unwrap();
+
+        let report = migrations().dry_run(&path).await.unwrap();
+        assert_eq!((report.from, report.to), (1, 3));
+        assert!(report.to_string().contains("+ schema_version = 3"));
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), original);
+
+        let report = migrations().migrate_file(&path).await.unwrap();
+        let backup = report.backup.unwrap();
+        assert_eq!(backup, temp_dir.path().join("app.yaml.v1.bak"));
+        assert_eq!(tokio::fs::read_to_string(&backup).await.unwrap(), original);
+        let migrated: Value = crate::loader::load_from_file(&path).await.unwrap();
+        assert_eq!(migrated["schema_version"], 3);
+        assert_eq!(migrated["database"]["pool"]["max"], 5);
+    }
+}
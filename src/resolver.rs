@@ -0,0 +1,239 @@
+//! Secret references resolved at load time
+//!
+//! A string value of the form `<scheme>://<reference>` is replaced by the
+//! secret it points to when a resolver for `<scheme>` is registered:
+//!
+//! ```yaml
+//! database:
+//!   password: "file:///run/secrets/db_pass"
+//!   token: "env://API_TOKEN"
+//! ```
+//!
+//! [`FileResolver`] and [`EnvResolver`] are built in, enable them with
+//! [`ConfigBuilder::resolve_secrets`](crate::ConfigBuilder::resolve_secrets).
+//! Other backends implement [`SecretResolver`] and are added with
+//! [`ConfigBuilder::secret_resolver`](crate::ConfigBuilder::secret_resolver).
+//!
+//! Resolution happens on every load and reload. When the config is saved,
+//! values that still match their reference are written back as the
+//! reference, so secrets never end up in the config file.
+
+use crate::value::{self, Value};
+use crate::{ConfigError, ConfigResult};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Resolves references of one URI scheme into secret values
+#[async_trait]
+pub trait SecretResolver: std::fmt::Debug + Send + Sync {
+    /// Scheme handled by this resolver, without `://`, e.g. `"file"`
+    fn scheme(&self) -> &str;
+
+    /// Resolve a reference, i.e. the part after `<scheme>://`
+    async fn resolve(&self, reference: &str) -> ConfigResult<String>;
+
+    /// File read by a reference, watched by hot-reload
+    fn watched_path(&self, _reference: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Reads `file://<path>` references, e.g. Kubernetes or Docker secret mounts
+///
+/// A single trailing newline is removed, as most tools write one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileResolver;
+
+#[async_trait]
+impl SecretResolver for FileResolver {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    async fn resolve(&self, reference: &str) -> ConfigResult<String> {
+        let content = tokio::fs::read_to_string(reference).await.map_err(|e| {
+            ConfigError::SecretReference(format!("Cannot read secret file '{reference}': {e}"))
+        })?;
+        let content = content.strip_suffix('\n').unwrap_or(&content);
+        Ok(content.strip_suffix('\r').unwrap_or(content).to_string())
+    }
+
+    fn watched_path(&self, reference: &str) -> Option<PathBuf> {
+        Some(PathBuf::from(reference))
+    }
+}
+
+/// Reads `env://<VAR>` references from the process environment
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvResolver;
+
+#[async_trait]
+impl SecretResolver for EnvResolver {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    async fn resolve(&self, reference: &str) -> ConfigResult<String> {
+        std::env::var(reference).map_err(|e| {
+            ConfigError::SecretReference(format!(
+                "Cannot read environment variable '{reference}': {e}"
+            ))
+        })
+    }
+}
+
+/// The built-in resolvers for `file://` and `env://`
+pub fn builtin_resolvers() -> Vec<Arc<dyn SecretResolver>> {
+    vec![Arc::new(FileResolver), Arc::new(EnvResolver)]
+}
+
+/// Split a string into its resolver and reference, if a resolver handles it
+fn find_reference<'a, 'r>(
+    text: &'a str,
+    resolvers: &'r [Arc<dyn SecretResolver>],
+) -> Option<(&'r dyn SecretResolver, &'a str)> {
+    let (scheme, reference) = text.split_once("://")?;
+    resolvers
+        .iter()
+        .find(|resolver| resolver.scheme() == scheme)
+        .map(|resolver| (resolver.as_ref(), reference))
+}
+
+/// Paths of the values in `tree` that are references
+pub(crate) fn reference_paths(tree: &Value, resolvers: &[Arc<dyn SecretResolver>]) -> Vec<String> {
+    references(tree, resolvers)
+        .into_iter()
+        .map(|(path, _, _)| path)
+        .collect()
+}
+
+/// References found in a value tree, as `(path, resolver, reference)`
+fn references<'r>(
+    tree: &Value,
+    resolvers: &'r [Arc<dyn SecretResolver>],
+) -> Vec<(String, &'r dyn SecretResolver, String)> {
+    value::flatten(tree)
+        .into_iter()
+        .filter_map(|(path, leaf)| {
+            let (resolver, reference) = find_reference(leaf.as_str()?, resolvers)?;
+            Some((path, resolver, reference.to_string()))
+        })
+        .collect()
+}
+
+/// Replace every reference in `tree` with its resolved value
+///
+/// Returns the files the resolved values were read from. All failing
+/// references are reported together.
+pub async fn resolve_tree(
+    tree: &mut Value,
+    resolvers: &[Arc<dyn SecretResolver>],
+) -> ConfigResult<Vec<PathBuf>> {
+    let mut watched = Vec::new();
+    let mut errors = Vec::new();
+
+    for (path, resolver, reference) in references(tree, resolvers) {
+        match resolver.resolve(&reference).await {
+            Ok(secret) => {
+                value::insert_path(tree, &path, Value::String(secret));
+                watched.extend(resolver.watched_path(&reference));
+            }
+            Err(err) => errors.push(format!("{path}: {err}")),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(watched)
+    } else {
+        Err(ConfigError::SecretReference(errors.join("; ")))
+    }
+}
+
+/// Put references from `previous` back into a tree that is about to be saved
+///
+/// A value is replaced by its reference if it still equals the secret the
+/// reference resolves to, or if the reference cannot be resolved anymore.
+/// Values that were changed in the meantime are kept. Returns the paths
+/// that hold a reference again.
+pub async fn restore_references(
+    tree: &mut Value,
+    previous: &Value,
+    resolvers: &[Arc<dyn SecretResolver>],
+) -> Vec<String> {
+    let mut restored = Vec::new();
+
+    for (path, resolver, reference) in references(previous, resolvers) {
+        let Some(current) = value::get_path(tree, &path) else {
+            continue;
+        };
+        let unchanged = match resolver.resolve(&reference).await {
+            Ok(secret) => value::scalar_to_string(current) == secret,
+            Err(_) => true,
+        };
+        if unchanged {
+            value::insert_path(
+                tree,
+                &path,
+                Value::String(format!("{}://{reference}", resolver.scheme())),
+            );
+            restored.push(path);
+        }
+    }
+
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_resolve_file_and_env() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let secret_file = temp_dir.path().join("db_pass");
+        tokio::fs::write(&secret_file, "hunter2\n").await.unwrap();
+        std::env::set_var("RUSTY_CONFIG_TEST_TOKEN", "t0ken");
+
+        let mut tree = json!({
+            "password": format!("file://{}", secret_file.display()),
+            "token": "env://RUSTY_CONFIG_TEST_TOKEN",
+            "homepage": "https://example.com",
+        });
+        let watched = resolve_tree(&mut tree, &builtin_resolvers()).await.unwrap();
+
+        assert_eq!(tree["password"], "hunter2");
+        assert_eq!(tree["token"], "t0ken");
+        assert_eq!(tree["homepage"], "https://example.com");
+        assert_eq!(watched, vec![secret_file]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_errors_are_collected() {
+        let mut tree = json!({
+            "a": "file:///nonexistent/rusty-config/secret",
+            "b": "env://RUSTY_CONFIG_TEST_UNSET_VARIABLE",
+        });
+        let err = resolve_tree(&mut tree, &builtin_resolvers())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("a: ") && err.contains("b: "), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_restore_references() {
+        std::env::set_var("RUSTY_CONFIG_TEST_RESTORE", "same");
+        let previous = json!({
+            "kept": "env://RUSTY_CONFIG_TEST_RESTORE",
+            "changed": "env://RUSTY_CONFIG_TEST_RESTORE",
+        });
+        let mut tree = json!({"kept": "same", "changed": "new value"});
+
+        let restored = restore_references(&mut tree, &previous, &builtin_resolvers()).await;
+        assert_eq!(restored, vec!["kept".to_string()]);
+        assert_eq!(tree["kept"], "env://RUSTY_CONFIG_TEST_RESTORE");
+        assert_eq!(tree["changed"], "new value");
+    }
+}
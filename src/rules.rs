@@ -0,0 +1,417 @@
+//! Cross-field and conditional validation rules
+//!
+//! Rules check the loaded value tree by dotted key path and are combined into
+//! larger rules with [`all`], [`any`] and [`when`]:
+//!
+//! ```rust
+//! use rusty_config::rules::{self, Rule};
+//!
+//! let rule: Rule = rules::all([
+//!     rules::when(
+//!         rules::is_true("tls.enabled"),
+//!         rules::required(["tls.cert_path", "tls.key_path"]),
+//!     ),
+//!     rules::less_or_equal("pool_min", "pool_max"),
+//!     rules::exactly_one(["url", "socket_path"]),
+//! ]);
+//!
+//! let tree = serde_json::json!({
+//!     "tls": { "enabled": true, "cert_path": "/etc/tls/cert.pem" },
+//!     "pool_min": 10,
+//!     "pool_max": 5,
+//!     "url": "postgres://localhost/db",
+//! });
+//! let fields: Vec<_> = rule
+//!     .validate(&tree)
+//!     .errors
+//!     .into_iter()
+//!     .map(|error| error.field)
+//!     .collect();
+//! assert_eq!(fields, ["tls.key_path", "pool_min"]);
+//! ```
+//!
+//! Attached with [`ConfigBuilder::rule`](crate::ConfigBuilder::rule), rules
+//! are checked on every load and reload before the document is deserialized.
+
+use crate::validator::{codes, ValidationContext, ValidationError, ValidationResult};
+use crate::value::{self, Value};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+type CheckFn = dyn Fn(&Value, &mut ValidationContext) + Send + Sync;
+
+/// A validation rule over a value tree
+#[derive(Clone)]
+pub struct Rule {
+    name: String,
+    check: Arc<CheckFn>,
+}
+
+impl Rule {
+    /// Create a rule that records its failures in a [`ValidationContext`]
+    pub fn new<F>(name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&Value, &mut ValidationContext) + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// Name of the rule, used in messages of combined rules
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check a tree and record failures in `ctx`
+    pub fn check(&self, tree: &Value, ctx: &mut ValidationContext) {
+        (self.check)(tree, ctx)
+    }
+
+    /// Check a tree and collect every failure
+    pub fn validate(&self, tree: &Value) -> ValidationResult {
+        let mut ctx = ValidationContext::new();
+        self.check(tree, &mut ctx);
+        ctx.into_result()
+    }
+}
+
+impl std::fmt::Debug for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Rule").field(&self.name).finish()
+    }
+}
+
+/// Whether `actual` equals `expected`, reading strings as numbers or booleans
+///
+/// Flat formats (INI, properties, dotenv) load every leaf as a string, so
+/// `"true"` matches `true` and `"8080"` matches `8080`.
+fn matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(text), Value::Bool(expected)) => value::parse_bool(text) == Some(*expected),
+        (Value::String(text), Value::Number(expected)) => {
+            value::parse_number(text).and_then(|number| number.as_f64()) == expected.as_f64()
+        }
+        (Value::Number(actual), Value::Number(expected)) => actual.as_f64() == expected.as_f64(),
+        (actual, expected) => actual == expected,
+    }
+}
+
+/// A number, or a string holding one
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => value::parse_number(text)?.as_f64(),
+        _ => None,
+    }
+}
+
+/// Whether a path holds a value, `null` counts as missing
+fn is_set(tree: &Value, path: &str) -> bool {
+    !matches!(value::get_path(tree, path), None | Some(Value::Null))
+}
+
+fn paths<I, S>(paths: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    paths.into_iter().map(Into::into).collect()
+}
+
+/// Every path must hold a value
+pub fn required<I, S>(fields: I) -> Rule
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let fields = paths(fields);
+    Rule::new(
+        format!("required({})", fields.join(", ")),
+        move |tree, ctx| {
+            for field in &fields {
+                if !is_set(tree, field) {
+                    ctx.add_error(ValidationError::templated(field, codes::REQUIRED, &[]));
+                }
+            }
+        },
+    )
+}
+
+/// The path must hold `true`
+pub fn is_true(field: impl Into<String>) -> Rule {
+    equals(field, Value::Bool(true))
+}
+
+/// The path must hold the given value
+pub fn equals(field: impl Into<String>, expected: impl Into<Value>) -> Rule {
+    let field = field.into();
+    let expected = expected.into();
+    Rule::new(format!("{field} == {expected}"), move |tree, ctx| {
+        if !value::get_path(tree, &field).is_some_and(|actual| matches(actual, &expected)) {
+            ctx.add_error(ValidationError::templated(
+                &field,
+                codes::EQUALS,
+                &[("expected", expected.to_string())],
+            ));
+        }
+    })
+}
+
+/// Every rule must hold
+pub fn all(rules: impl IntoIterator<Item = Rule>) -> Rule {
+    let rules: Vec<Rule> = rules.into_iter().collect();
+    let names: Vec<_> = rules.iter().map(Rule::name).collect();
+    let name = format!("all({})", names.join(", "));
+    Rule::new(name, move |tree, ctx| {
+        for rule in &rules {
+            rule.check(tree, ctx);
+        }
+    })
+}
+
+/// At least one rule must hold
+///
+/// Fails with a single `any` error that lists the failures of every
+/// alternative.
+pub fn any(rules: impl IntoIterator<Item = Rule>) -> Rule {
+    let rules: Vec<Rule> = rules.into_iter().collect();
+    let names: Vec<_> = rules.iter().map(Rule::name).collect();
+    let name = format!("any({})", names.join(", "));
+    Rule::new(name, move |tree, ctx| {
+        let mut failures = Vec::new();
+        for rule in &rules {
+            let result = rule.validate(tree);
+            if result.is_valid {
+                return;
+            }
+            failures.extend(result.errors.iter().map(ToString::to_string));
+        }
+        ctx.add_error(ValidationError::templated(
+            "",
+            codes::ANY,
+            &[("failures", failures.join("; "))],
+        ));
+    })
+}
+
+/// `rule` must hold whenever `condition` holds
+pub fn when(condition: Rule, rule: Rule) -> Rule {
+    let name = format!("when({}, {})", condition.name, rule.name);
+    Rule::new(name, move |tree, ctx| {
+        if condition.validate(tree).is_valid {
+            rule.check(tree, ctx);
+        }
+    })
+}
+
+/// When `field` is set and not `false`, every path in `required` must be set
+pub fn requires<I, S>(field: impl Into<String>, required: I) -> Rule
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let field = field.into();
+    let required = paths(required);
+    let name = format!("{field} requires({})", required.join(", "));
+    Rule::new(name, move |tree, ctx| {
+        let disabled = value::get_path(tree, &field)
+            .is_some_and(|actual| matches(actual, &Value::Bool(false)));
+        if !is_set(tree, &field) || disabled {
+            return;
+        }
+        for path in &required {
+            if !is_set(tree, path) {
+                ctx.add_error(ValidationError::templated(
+                    path,
+                    codes::REQUIRES,
+                    &[("trigger", field.clone())],
+                ));
+            }
+        }
+    })
+}
+
+/// At most one of the paths may be set
+pub fn mutually_exclusive<I, S>(fields: I) -> Rule
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let fields = paths(fields);
+    let name = format!("mutually_exclusive({})", fields.join(", "));
+    Rule::new(name, move |tree, ctx| {
+        let set: Vec<_> = fields.iter().filter(|field| is_set(tree, field)).collect();
+        if set.len() > 1 {
+            for field in &set[1..] {
+                ctx.add_error(ValidationError::templated(
+                    field.as_str(),
+                    codes::MUTUALLY_EXCLUSIVE,
+                    &[("other", set[0].clone())],
+                ));
+            }
+        }
+    })
+}
+
+/// Exactly one of the paths must be set
+pub fn exactly_one<I, S>(fields: I) -> Rule
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let fields = paths(fields);
+    let exclusive = mutually_exclusive(fields.clone());
+    let name = format!("exactly_one({})", fields.join(", "));
+    Rule::new(name, move |tree, ctx| {
+        if fields.iter().any(|field| is_set(tree, field)) {
+            exclusive.check(tree, ctx);
+        } else {
+            ctx.add_error(ValidationError::templated(
+                "",
+                codes::EXACTLY_ONE,
+                &[("fields", fields.join(", "))],
+            ));
+        }
+    })
+}
+
+/// Compare two numeric or string values, skipped if either is missing
+///
+/// Strings that hold numbers are compared as numbers.
+fn compare(
+    left: String,
+    right: String,
+    allowed: &'static [Ordering],
+    symbol: &'static str,
+) -> Rule {
+    let name = format!("{left} {symbol} {right}");
+    Rule::new(name, move |tree, ctx| {
+        let (Some(a), Some(b)) = (value::get_path(tree, &left), value::get_path(tree, &right))
+        else {
+            return;
+        };
+        let ordering = match (as_number(a), as_number(b), a, b) {
+            (Some(a), Some(b), _, _) => a.partial_cmp(&b),
+            (_, _, Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        if !ordering.is_some_and(|ordering| allowed.contains(&ordering)) {
+            ctx.add_error(ValidationError::templated(
+                &left,
+                codes::COMPARE,
+                &[
+                    ("operator", symbol.to_string()),
+                    ("right", right.clone()),
+                    ("left_value", a.to_string()),
+                    ("right_value", b.to_string()),
+                ],
+            ));
+        }
+    })
+}
+
+/// `left <= right`
+pub fn less_or_equal(left: impl Into<String>, right: impl Into<String>) -> Rule {
+    compare(
+        left.into(),
+        right.into(),
+        &[Ordering::Less, Ordering::Equal],
+        "<=",
+    )
+}
+
+/// `left < right`
+pub fn less_than(left: impl Into<String>, right: impl Into<String>) -> Rule {
+    compare(left.into(), right.into(), &[Ordering::Less], "<")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codes(rule: &Rule, tree: &Value) -> Vec<(String, String)> {
+        rule.validate(tree)
+            .errors
+            .into_iter()
+            .map(|error| (error.field, error.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_conditional_and_exclusive_rules() {
+        let tls = requires("tls.enabled", ["tls.cert_path", "tls.key_path"]);
+        assert!(codes(&tls, &json!({"tls": {"enabled": false}})).is_empty());
+        assert_eq!(
+            codes(&tls, &json!({"tls": {"enabled": true, "key_path": "k"}})),
+            [("tls.cert_path".to_string(), "requires".to_string())]
+        );
+
+        let target = exactly_one(["url", "socket_path"]);
+        assert!(codes(&target, &json!({"url": "u"})).is_empty());
+        assert_eq!(
+            codes(&target, &json!({"url": "u", "socket_path": "s"})),
+            [("socket_path".to_string(), "mutually_exclusive".to_string())]
+        );
+        assert_eq!(
+            codes(&target, &json!({})),
+            [(String::new(), "exactly_one".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_any_and_compare() {
+        let pool = less_or_equal("pool.min", "pool.max");
+        assert!(codes(&pool, &json!({"pool": {"min": 1, "max": 1}})).is_empty());
+        assert_eq!(
+            codes(&pool, &json!({"pool": {"min": 2.5, "max": 1}})),
+            [("pool.min".to_string(), "compare".to_string())]
+        );
+
+        assert!(codes(&less_than("a", "b"), &json!({"a": "abc", "b": "abd"})).is_empty());
+
+        let auth = any([required(["token"]), required(["user", "password"])]);
+        assert!(codes(&auth, &json!({"user": "u", "password": "p"})).is_empty());
+        let result = auth.validate(&json!({"user": "u"}));
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].code, "any");
+        assert!(result.errors[0].message.contains("password"));
+    }
+
+    #[cfg(feature = "properties")]
+    #[test]
+    fn test_rules_on_properties_strings() {
+        let tree: Value = crate::loader::load_from_bytes(
+            b"pool_min=10\npool_max=9\ntls.enabled=true\n".to_vec(),
+            &crate::loader::ConfigFormat::Properties,
+        )
+        .unwrap();
+
+        let pool = less_or_equal("pool_min", "pool_max");
+        assert_eq!(
+            codes(&pool, &tree),
+            [("pool_min".to_string(), "compare".to_string())]
+        );
+        let tls = when(is_true("tls.enabled"), required(["tls.cert"]));
+        assert_eq!(
+            codes(&tls, &tree),
+            [("tls.cert".to_string(), "required".to_string())]
+        );
+    }
+
+    #[cfg(feature = "ini")]
+    #[test]
+    fn test_rules_on_ini_strings() {
+        let tree: Value = crate::loader::load_from_bytes(
+            b"[tls]\nenabled = false\n[pool]\nmin = 2\nmax = 10\n".to_vec(),
+            &crate::loader::ConfigFormat::Ini,
+        )
+        .unwrap();
+
+        assert!(codes(&less_or_equal("pool.min", "pool.max"), &tree).is_empty());
+        assert!(codes(&equals("pool.max", 10), &tree).is_empty());
+        assert!(codes(&requires("tls.enabled", ["tls.cert"]), &tree).is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+//! JSON Schema for config files
+//!
+//! With the `schema` feature, [`json_schema`] generates a JSON Schema from a
+//! config type deriving [`JsonSchema`]. Validation attributes become schema
+//! constraints, so editors and CI linters check the same limits as the code:
+//!
+//! ```rust
+//! # #[cfg(feature = "schema")] {
+//! use rusty_config::schema::{json_schema, JsonSchema};
+//!
+//! #[derive(serde::Deserialize, JsonSchema)]
+//! struct ServerConfig {
+//!     #[validate(length(min = 1))]
+//!     host: String,
+//!     #[validate(range(min = 1, max = 65535))]
+//!     port: u32,
+//!     #[validate(email)]
+//!     admin: String,
+//! }
+//!
+//! let schema = json_schema::<ServerConfig>();
+//! assert_eq!(schema["properties"]["port"]["maximum"], 65535);
+//! # }
+//! ```
+//!
+//! [`add_schema_reference`] links a config file to its schema in the way
+//! editors for each format understand, see
+//! [`ConfigBuilder::schema_url`](crate::ConfigBuilder::schema_url).
+
+use crate::loader::ConfigFormat;
+#[cfg(feature = "schema-validation")]
+use crate::validator::{ValidationError, ValidationResult};
+use crate::value::Value;
+use crate::{ConfigError, ConfigResult};
+
+#[cfg(feature = "schema")]
+pub use schemars::JsonSchema;
+
+/// Generate the JSON Schema of a config type
+#[cfg(feature = "schema")]
+pub fn json_schema<T: JsonSchema>() -> Value {
+    schemars::schema_for!(T).to_value()
+}
+
+/// Write the JSON Schema of a config type to a file
+#[cfg(feature = "schema")]
+pub async fn write_schema<T, P>(path: P) -> ConfigResult<()>
+where
+    T: JsonSchema,
+    P: AsRef<std::path::Path>,
+{
+    let schema = serde_json::to_string_pretty(&json_schema::<T>())?;
+    tokio::fs::write(path, schema + "\n").await?;
+    Ok(())
+}
+
+/// Checks value trees against a JSON Schema
+///
+/// Useful for configs written by teams that do not share the Rust types.
+/// Used as a load-time gate with
+/// [`ConfigBuilder::validate_schema`](crate::ConfigBuilder::validate_schema),
+/// the loaded document is checked after defaults, overrides and secrets
+/// were applied and before it is deserialized.
+///
+/// Messages name the failed keyword but never the offending value, so a
+/// decrypted secret that breaks `minLength` or `pattern` does not end up in
+/// the error. Strings where the schema asks for an integer, number or
+/// boolean are read the way deserialization reads them, as flat formats
+/// (INI, properties, dotenv) load every value as a string.
+#[cfg(feature = "schema-validation")]
+#[derive(Debug)]
+pub struct SchemaValidator {
+    validator: jsonschema::Validator,
+}
+
+#[cfg(feature = "schema-validation")]
+impl SchemaValidator {
+    /// Compile a schema
+    pub fn new(schema: &Value) -> ConfigResult<Self> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|err| ConfigError::InvalidFormat(format!("Invalid JSON Schema: {err}")))?;
+        Ok(Self { validator })
+    }
+
+    /// Read and compile a schema file in any supported format
+    pub async fn from_file<P: AsRef<std::path::Path>>(path: P) -> ConfigResult<Self> {
+        let schema: Value = crate::loader::load_from_file(path.as_ref()).await?;
+        Self::new(&schema)
+    }
+
+    /// Check a value tree and collect every violation
+    ///
+    /// `field` is the JSON pointer of the offending value (`/servers/2/port`,
+    /// empty for the document root) and `code` the schema keyword that
+    /// failed, e.g. `maximum` or `required`.
+    pub fn validate(&self, tree: &Value) -> ValidationResult {
+        let coerced = self.coerce_strings(tree);
+        let tree = coerced.as_ref().unwrap_or(tree);
+
+        let mut result = ValidationResult::new();
+        for error in self.validator.iter_errors(tree) {
+            let schema_path = error.schema_path.as_str();
+            let keyword = schema_path.rsplit('/').next().unwrap_or(schema_path);
+            result.add_error(
+                ValidationError::new(error.instance_path.as_str(), error.masked().to_string())
+                    .with_code(keyword),
+            );
+        }
+        result
+    }
+
+    /// Copy of `tree` with strings converted where the schema wants another type
+    ///
+    /// `None` when no string needed converting.
+    fn coerce_strings(&self, tree: &Value) -> Option<Value> {
+        use jsonschema::error::{TypeKind, ValidationErrorKind};
+        use jsonschema::JsonType;
+
+        let mut coerced: Option<Value> = None;
+        for error in self.validator.iter_errors(tree) {
+            let (ValidationErrorKind::Type { kind }, Value::String(text)) =
+                (&error.kind, error.instance.as_ref())
+            else {
+                continue;
+            };
+            let allows = |ty: JsonType| match kind {
+                TypeKind::Single(single) => *single == ty,
+                TypeKind::Multiple(set) => set.contains(ty),
+            };
+            let number = crate::value::parse_number(text);
+            let value = if let Some(number) = number
+                .clone()
+                .filter(|number| !number.is_f64() && allows(JsonType::Integer))
+            {
+                Value::Number(number)
+            } else if let Some(number) = number.filter(|_| allows(JsonType::Number)) {
+                Value::Number(number)
+            } else if let Some(flag) =
+                crate::value::parse_bool(text).filter(|_| allows(JsonType::Boolean))
+            {
+                Value::Bool(flag)
+            } else {
+                continue;
+            };
+
+            let target = coerced.get_or_insert_with(|| tree.clone());
+            if let Some(slot) = target.pointer_mut(error.instance_path.as_str()) {
+                *slot = value;
+            }
+        }
+        coerced
+    }
+
+    /// Check a value tree, failing with all violations
+    pub fn check(&self, tree: &Value) -> ConfigResult<()> {
+        let result = self.validate(tree);
+        if result.is_valid {
+            Ok(())
+        } else {
+            Err(ConfigError::ValidationErrors(result.errors))
+        }
+    }
+}
+
+/// The comment line that links a file of the given format to a schema
+///
+/// YAML uses the yaml-language-server modeline, TOML the Taplo directive.
+/// Other formats have no comment convention and return `None`.
+pub fn schema_comment(format: &ConfigFormat, schema_url: &str) -> Option<String> {
+    match format {
+        ConfigFormat::Yaml => Some(format!("# yaml-language-server: $schema={schema_url}")),
+        ConfigFormat::Toml => Some(format!("#:schema {schema_url}")),
+        _ => None,
+    }
+}
+
+/// Link config content to a schema
+///
+/// YAML and TOML get a comment line in front, JSON objects get a leading
+/// `"$schema"` property. Content in other formats is returned unchanged.
+pub fn add_schema_reference(
+    content: &str,
+    format: &ConfigFormat,
+    schema_url: &str,
+) -> ConfigResult<String> {
+    if let Some(comment) = schema_comment(format, schema_url) {
+        return Ok(format!("{comment}\n{content}"));
+    }
+    if *format != ConfigFormat::Json {
+        return Ok(content.to_string());
+    }
+
+    let document: Value = serde_json::from_str(content)?;
+    let Value::Object(fields) = document else {
+        return Err(ConfigError::InvalidFormat(
+            "A schema reference needs a JSON object".to_string(),
+        ));
+    };
+    let mut linked = serde_json::Map::new();
+    linked.insert("$schema".to_string(), Value::String(schema_url.to_string()));
+    linked.extend(fields.into_iter().filter(|(key, _)| key != "$schema"));
+    Ok(serde_json::to_string_pretty(&Value::Object(linked))? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/app.schema.json";
+
+    #[test]
+    fn test_add_schema_reference() {
+        let yaml = add_schema_reference("port: 80\n", &ConfigFormat::Yaml, URL).unwrap();
+        assert_eq!(
+            yaml,
+            format!("# yaml-language-server: $schema={URL}\nport: 80\n")
+        );
+        let toml = add_schema_reference("port = 80\n", &ConfigFormat::Toml, URL).unwrap();
+        assert!(toml.starts_with(&format!("#:schema {URL}\n")));
+
+        let json = add_schema_reference(
+            r#"{"port": 80, "$schema": "old"}"#,
+            &ConfigFormat::Json,
+            URL,
+        )
+        .unwrap();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+        let keys: Vec<_> = parsed.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["$schema", "port"]);
+        assert_eq!(parsed["$schema"], URL);
+
+        let ini = add_schema_reference("port=80\n", &ConfigFormat::Ini, URL).unwrap();
+        assert_eq!(ini, "port=80\n");
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[test]
+    fn test_schema_validator_reports_pointers_and_keywords() {
+        let validator = SchemaValidator::new(&serde_json::json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "servers": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "port": { "type": "integer", "maximum": 65535 } }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let tree = serde_json::json!({
+            "servers": [{ "port": 80 }, { "port": 70000 }, { "port": "http" }]
+        });
+        let mut errors: Vec<_> = validator
+            .validate(&tree)
+            .errors
+            .into_iter()
+            .map(|error| (error.field, error.code))
+            .collect();
+        errors.sort();
+        assert_eq!(
+            errors,
+            [
+                (String::new(), "required".to_string()),
+                ("/servers/1/port".to_string(), "maximum".to_string()),
+                ("/servers/2/port".to_string(), "type".to_string()),
+            ]
+        );
+        assert!(validator
+            .check(&serde_json::json!({ "name": "ok" }))
+            .is_ok());
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[test]
+    fn test_schema_errors_leave_out_values() {
+        let validator = SchemaValidator::new(&serde_json::json!({
+            "properties": {
+                "password": { "type": "string", "minLength": 12, "pattern": "[0-9]" }
+            }
+        }))
+        .unwrap();
+
+        let errors = validator
+            .validate(&serde_json::json!({ "password": "hunter" }))
+            .errors;
+        assert_eq!(errors.len(), 2);
+        for error in errors {
+            assert_eq!(error.field, "/password");
+            assert!(!error.message.contains("hunter"), "{}", error.message);
+        }
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[test]
+    fn test_schema_reads_strings_as_requested_types() {
+        let validator = SchemaValidator::new(&serde_json::json!({
+            "properties": {
+                "port": { "type": "integer", "maximum": 65535 },
+                "ratio": { "type": "number" },
+                "debug": { "type": "boolean" },
+                "name": { "type": "string" },
+                "limit": { "type": ["integer", "null"] }
+            }
+        }))
+        .unwrap();
+
+        let flat = serde_json::json!({
+            "port": "8080",
+            "ratio": "0.5",
+            "debug": "true",
+            "name": "12345",
+            "limit": "10"
+        });
+        assert!(validator.check(&flat).is_ok());
+
+        let errors = validator
+            .validate(&serde_json::json!({ "port": "70000", "debug": "maybe", "ratio": "1.5" }))
+            .errors;
+        let mut failed: Vec<_> = errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.code.as_str()))
+            .collect();
+        failed.sort();
+        assert_eq!(failed, [("/debug", "type"), ("/port", "maximum")]);
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_schema_includes_validation_constraints() {
+        #[derive(serde::Deserialize, JsonSchema)]
+        #[allow(dead_code)]
+        struct Database {
+            #[validate(url)]
+            url: String,
+            #[validate(length(min = 3, max = 32))]
+            user: String,
+            password: crate::Secret<String>,
+            #[validate(range(min = 1, max = 100))]
+            pool_size: u32,
+        }
+
+        let schema = json_schema::<Database>();
+        let properties = &schema["properties"];
+        assert_eq!(properties["url"]["format"], "uri");
+        assert_eq!(properties["user"]["minLength"], 3);
+        assert_eq!(properties["user"]["maxLength"], 32);
+        assert_eq!(properties["pool_size"]["minimum"], 1);
+        assert_eq!(properties["pool_size"]["maximum"], 100);
+        assert_eq!(properties["password"]["type"], "string");
+        assert_eq!(properties["password"]["writeOnly"], true);
+    }
+}
@@ -0,0 +1,242 @@
+//! Wrapper type for sensitive config values
+
+use crate::{value, ConfigResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use zeroize::Zeroize;
+
+/// A sensitive config value, such as a password or an API token
+///
+/// `Secret<T>` deserializes transparently from `T`. `Debug` and `Display`
+/// print `***`, serializing writes `"***"` unless it happens inside
+/// [`expose_secrets`], and the value is zeroized when dropped.
+///
+/// Saving a config exposes secrets automatically. With an encryption keyring
+/// (`encryption` feature) secret fields are written encrypted, see
+/// [`secret_paths`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Wrap a value
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Access the wrapped value
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the value
+    ///
+    /// The secret is left holding `T::default()`, so nothing but the
+    /// returned value remains to be zeroized.
+    pub fn into_inner(mut self) -> T
+    where
+        T: Default,
+    {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Zeroize> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize> std::fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Secret<T>
+where
+    T: Zeroize + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+impl<T> Serialize for Secret<T>
+where
+    T: Zeroize + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match current_mode() {
+            SerializeMode::Redacted => serializer.serialize_str(REDACTED),
+            SerializeMode::Plain => self.0.serialize(serializer),
+            SerializeMode::Mark => serializer.serialize_str(MARKER),
+        }
+    }
+}
+
+/// Secrets have the schema of their value, marked `writeOnly`
+#[cfg(feature = "schema")]
+impl<T> schemars::JsonSchema for Secret<T>
+where
+    T: Zeroize + schemars::JsonSchema,
+{
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        T::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut schema = generator.subschema_for::<T>();
+        schema.insert("writeOnly".to_string(), true.into());
+        schema
+    }
+}
+
+/// Text shown in place of a secret
+pub const REDACTED: &str = "***";
+
+/// Placeholder written for secrets while locating them
+const MARKER: &str = "\u{0}rusty-config:secret\u{0}";
+
+/// How [`Secret`] values are written while serializing
+#[derive(Clone, Copy)]
+enum SerializeMode {
+    /// Write [`REDACTED`] instead of the value
+    Redacted,
+    /// Write the plain value
+    Plain,
+    /// Write a marker instead of the value, to find where secrets are
+    Mark,
+}
+
+thread_local! {
+    static MODE: Cell<SerializeMode> = const { Cell::new(SerializeMode::Redacted) };
+}
+
+fn current_mode() -> SerializeMode {
+    MODE.with(Cell::get)
+}
+
+/// Restores the previous mode, also when serialization panics
+struct ModeGuard(SerializeMode);
+
+impl Drop for ModeGuard {
+    fn drop(&mut self) {
+        MODE.with(|mode| mode.set(self.0));
+    }
+}
+
+/// Run `f` with [`Secret`] values serialized in the given mode
+///
+/// Serialization is synchronous, so a thread-local scope is sufficient.
+fn with_mode<R>(mode: SerializeMode, f: impl FnOnce() -> R) -> R {
+    let _guard = ModeGuard(MODE.with(|current| current.replace(mode)));
+    f()
+}
+
+/// Run `f` with [`Secret`] values serialized in plain text
+///
+/// Only serialization on the current thread inside `f` is affected:
+///
+/// ```rust
+/// use rusty_config::secret::{expose_secrets, Secret};
+///
+/// let token = Secret::new("s3cr3t".to_string());
+/// assert_eq!(serde_json::to_string(&token)?, r#""***""#);
+/// assert_eq!(expose_secrets(|| serde_json::to_string(&token))?, r#""s3cr3t""#);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn expose_secrets<R>(f: impl FnOnce() -> R) -> R {
+    with_mode(SerializeMode::Plain, f)
+}
+
+/// Serialize `data` into a value tree with every [`Secret`] replaced by [`REDACTED`]
+///
+/// Unlike plain serialization this is not affected by an enclosing
+/// [`expose_secrets`].
+pub fn redacted_value<T>(data: &T) -> ConfigResult<value::Value>
+where
+    T: Serialize,
+{
+    Ok(with_mode(SerializeMode::Redacted, || {
+        serde_json::to_value(data)
+    })?)
+}
+
+/// Find the dotted key paths of all [`Secret`] values in `data`
+pub fn secret_paths<T>(data: &T) -> ConfigResult<Vec<String>>
+where
+    T: Serialize,
+{
+    let marked = with_mode(SerializeMode::Mark, || serde_json::to_value(data))?;
+    Ok(value::flatten(&marked)
+        .into_iter()
+        .filter(|(_, leaf)| leaf.as_str() == Some(MARKER))
+        .map(|(path, _)| path)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Database {
+        user: String,
+        password: Secret<String>,
+    }
+
+    #[test]
+    fn test_secret_roundtrip_and_debug() {
+        let db: Database =
+            serde_json::from_str(r#"{"user": "app", "password": "hunter2"}"#).unwrap();
+        assert_eq!(db.password.expose(), "hunter2");
+        assert!(!format!("{db:?}").contains("hunter2"));
+        assert_eq!(db.password.to_string(), "***");
+
+        let json = serde_json::to_value(&db).unwrap();
+        assert_eq!(json["password"], "***");
+        let json = expose_secrets(|| serde_json::to_value(&db)).unwrap();
+        assert_eq!(json["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let secret = Secret::new("token".to_string());
+        assert_eq!(secret.into_inner(), "token");
+    }
+
+    #[test]
+    fn test_secret_paths() {
+        let db = Database {
+            user: "app".to_string(),
+            password: Secret::new("hunter2".to_string()),
+        };
+        assert_eq!(secret_paths(&db).unwrap(), vec!["password".to_string()]);
+        assert_eq!(
+            secret_paths(&vec![Secret::new(1), Secret::new(2)]).unwrap(),
+            vec!["0".to_string(), "1".to_string()]
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! SOPS-encrypted YAML and JSON files
+//!
+//! Files written by [SOPS](https://github.com/getsops/sops) keep their
+//! structure, encrypt every value and carry a `sops` metadata block:
+//!
+//! ```yaml
+//! database:
+//!   password: ENC[AES256_GCM,data:...,iv:...,tag:...,type:str]
+//! sops:
+//!   age:
+//!     - recipient: age1...
+//!       enc: |
+//!         -----BEGIN AGE ENCRYPTED FILE-----
+//!         ...
+//!   lastmodified: "2024-05-01T12:00:00Z"
+//!   mac: ENC[AES256_GCM,data:...,iv:...,tag:...,type:str]
+//! ```
+//!
+//! [`load_from_file`](crate::loader::load_from_file) detects the metadata
+//! block, decrypts the data key with a local age identity or PGP key, then
+//! decrypts all values and verifies the MAC before deserializing. Keys are
+//! taken from [`SopsKeys`], by default from the same places SOPS looks.
+//!
+//! Only decryption is supported: saving over a SOPS file fails instead of
+//! writing the secrets in plain text. YAML comments encrypted by SOPS are
+//! not supported, such files fail the MAC check.
+
+use crate::value::Value;
+use crate::{ConfigError, ConfigResult};
+use aes_gcm::aead::consts::U32;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::aes::Aes256;
+use aes_gcm::AesGcm;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sha2::{Digest, Sha512};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// AES-256-GCM with the 256-bit nonces SOPS uses
+type SopsCipher = AesGcm<Aes256, U32>;
+
+/// Keys that may decrypt the data key of a SOPS file
+#[derive(Clone, Default)]
+pub struct SopsKeys {
+    age: Vec<age::x25519::Identity>,
+    pgp_key_files: Vec<PathBuf>,
+    gpg_keyring: bool,
+}
+
+impl SopsKeys {
+    /// Create an empty key set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect keys the way the `sops` command does
+    ///
+    /// age identities are read from `SOPS_AGE_KEY`, the file named by
+    /// `SOPS_AGE_KEY_FILE` and `$XDG_CONFIG_HOME/sops/age/keys.txt`
+    /// (`~/.config/sops/age/keys.txt`). PGP keys are used from the GnuPG
+    /// keyring.
+    pub fn from_env() -> ConfigResult<Self> {
+        let mut keys = Self::new().gpg_keyring(true);
+        if let Ok(identities) = std::env::var("SOPS_AGE_KEY") {
+            keys = keys.age_key(&identities)?;
+        }
+        if let Ok(path) = std::env::var("SOPS_AGE_KEY_FILE") {
+            keys = keys.age_key_file(path)?;
+        }
+
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+        if let Some(default_file) = config_home.map(|dir| dir.join("sops/age/keys.txt")) {
+            if default_file.exists() {
+                keys = keys.age_key_file(default_file)?;
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Add age identities (`AGE-SECRET-KEY-1...`), one per line
+    ///
+    /// Empty lines and `#` comments are ignored, as in `age-keygen` output.
+    pub fn age_key(mut self, identities: &str) -> ConfigResult<Self> {
+        for line in identities.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let identity = line
+                .parse()
+                .map_err(|err| ConfigError::Encryption(format!("Invalid age identity: {err}")))?;
+            self.age.push(identity);
+        }
+        Ok(self)
+    }
+
+    /// Add the age identities from a key file
+    pub fn age_key_file<P: AsRef<Path>>(self, path: P) -> ConfigResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            ConfigError::Encryption(format!(
+                "Cannot read age key file '{}': {err}",
+                path.display()
+            ))
+        })?;
+        self.age_key(&content)
+    }
+
+    /// Add an armored PGP secret key file, used through `gpg`
+    ///
+    /// The key is imported into a temporary keyring for each decryption, so
+    /// the user's keyring is not touched.
+    pub fn pgp_key_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.pgp_key_files.push(path.into());
+        self
+    }
+
+    /// Also try the PGP keys in the user's GnuPG keyring
+    pub fn gpg_keyring(mut self, enabled: bool) -> Self {
+        self.gpg_keyring = enabled;
+        self
+    }
+
+    /// Decrypt the data key from the `sops` metadata block
+    fn data_key(&self, metadata: &Value) -> ConfigResult<Vec<u8>> {
+        let groups = match metadata.get("key_groups").and_then(Value::as_array) {
+            Some(groups) => {
+                let threshold = metadata.get("shamir_threshold").and_then(Value::as_u64);
+                if groups.len() > 1 && threshold.unwrap_or(groups.len() as u64) > 1 {
+                    return Err(ConfigError::Encryption(
+                        "SOPS files with Shamir key groups are not supported".to_string(),
+                    ));
+                }
+                groups.iter().collect()
+            }
+            None => vec![metadata],
+        };
+
+        let mut errors = Vec::new();
+        for group in groups {
+            for entry in entries(group, "age") {
+                match self.decrypt_age(entry) {
+                    Ok(key) => return Ok(key),
+                    Err(err) => errors.push(format!("age: {err}")),
+                }
+            }
+            for entry in entries(group, "pgp") {
+                match self.decrypt_pgp(entry) {
+                    Ok(key) => return Ok(key),
+                    Err(err) => errors.push(format!("pgp: {err}")),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            errors.push("the file has no age or PGP keys".to_string());
+        }
+        Err(ConfigError::Encryption(format!(
+            "Cannot decrypt the SOPS data key: {}",
+            errors.join("; ")
+        )))
+    }
+
+    fn decrypt_age(&self, entry: &Value) -> Result<Vec<u8>, String> {
+        if self.age.is_empty() {
+            return Err("no age identity available".to_string());
+        }
+        let enc = entry_field(entry, "enc")?;
+        let decryptor = age::Decryptor::new(age::armor::ArmoredReader::new(enc.as_bytes()))
+            .map_err(|err| err.to_string())?;
+        let mut reader = decryptor
+            .decrypt(
+                self.age
+                    .iter()
+                    .map(|identity| identity as &dyn age::Identity),
+            )
+            .map_err(|err| err.to_string())?;
+
+        let mut key = Vec::new();
+        reader
+            .read_to_end(&mut key)
+            .map_err(|err| err.to_string())?;
+        Ok(key)
+    }
+
+    fn decrypt_pgp(&self, entry: &Value) -> Result<Vec<u8>, String> {
+        let enc = entry_field(entry, "enc")?;
+        let mut errors = Vec::new();
+
+        for key_file in &self.pgp_key_files {
+            match gpg_decrypt_with_key_file(key_file, enc) {
+                Ok(key) => return Ok(key),
+                Err(err) => errors.push(format!("{}: {err}", key_file.display())),
+            }
+        }
+        if self.gpg_keyring {
+            match gpg(None, &["--decrypt"], Some(enc.as_bytes())) {
+                Ok(key) => return Ok(key),
+                Err(err) => errors.push(format!("keyring: {err}")),
+            }
+        }
+
+        if errors.is_empty() {
+            errors.push("no PGP key available".to_string());
+        }
+        Err(errors.join(", "))
+    }
+}
+
+impl std::fmt::Debug for SopsKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SopsKeys")
+            .field("age", &format_args!("[{} identities]", self.age.len()))
+            .field("pgp_key_files", &self.pgp_key_files)
+            .field("gpg_keyring", &self.gpg_keyring)
+            .finish()
+    }
+}
+
+/// The key entries of one kind (`age`, `pgp`) in a key group
+fn entries<'a>(group: &'a Value, kind: &str) -> impl Iterator<Item = &'a Value> {
+    group
+        .get(kind)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn entry_field<'a>(entry: &'a Value, field: &str) -> Result<&'a str, String> {
+    entry
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("key entry without '{field}'"))
+}
+
+/// Import a PGP key into a temporary keyring and decrypt with it
+fn gpg_decrypt_with_key_file(key_file: &Path, message: &str) -> Result<Vec<u8>, String> {
+    let home = std::env::temp_dir().join(format!("rusty-config-gpg-{}", uuid::Uuid::new_v4()));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&home).map_err(|err| err.to_string())?;
+
+    let key_file = key_file.to_string_lossy();
+    let result = gpg(Some(&home), &["--import", &key_file], None)
+        .and_then(|_| gpg(Some(&home), &["--decrypt"], Some(message.as_bytes())));
+
+    // Stop the agent gpg started for the temporary keyring
+    let _ = Command::new("gpgconf")
+        .arg("--homedir")
+        .arg(&home)
+        .args(["--kill", "gpg-agent"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = std::fs::remove_dir_all(&home);
+    result
+}
+
+/// Run `gpg` non-interactively and return its output
+fn gpg(home: Option<&Path>, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let mut command = Command::new("gpg");
+    if let Some(home) = home {
+        command.arg("--homedir").arg(home);
+    }
+    command
+        .args(["--batch", "--quiet", "--pinentry-mode", "loopback"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("cannot run gpg: {err}"))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(input) = input {
+        stdin.write_all(input).map_err(|err| err.to_string())?;
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Check whether a parsed document carries a SOPS metadata block
+pub fn is_sops_document(tree: &Value) -> bool {
+    tree.get("sops")
+        .is_some_and(|metadata| metadata.get("mac").is_some())
+}
+
+/// Decrypt a SOPS document and verify its MAC
+///
+/// Returns the document without the `sops` metadata block.
+pub fn decrypt_document(mut tree: Value, keys: &SopsKeys) -> ConfigResult<Value> {
+    let metadata = tree
+        .as_object_mut()
+        .and_then(|map| map.shift_remove("sops"))
+        .ok_or_else(|| ConfigError::Encryption("Missing SOPS metadata".to_string()))?;
+    let data_key = keys.data_key(&metadata)?;
+
+    let text = |field: &str| metadata.get(field).and_then(Value::as_str);
+    let mut walker = Walker {
+        key: &data_key,
+        unencrypted_suffix: text("unencrypted_suffix"),
+        encrypted_suffix: text("encrypted_suffix"),
+        mac_only_encrypted: metadata
+            .get("mac_only_encrypted")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        hash: Sha512::new(),
+    };
+    walker.walk(&mut tree, &mut Vec::new())?;
+    let computed: String = walker
+        .hash
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect();
+
+    let mac = text("mac").unwrap_or_default();
+    let last_modified = text("lastmodified").unwrap_or_default();
+    let expected = decrypt_value(mac, &data_key, last_modified)?;
+    if expected.as_str() != Some(computed.as_str()) {
+        return Err(ConfigError::Encryption(
+            "SOPS MAC mismatch: the file was modified without sops".to_string(),
+        ));
+    }
+
+    Ok(tree)
+}
+
+/// Decrypt `bytes` if they hold a SOPS document in a format SOPS writes
+///
+/// Without explicit keys, [`SopsKeys::from_env`] is used.
+pub(crate) fn decrypt_content(
+    bytes: &[u8],
+    format: &crate::loader::ConfigFormat,
+    keys: Option<&SopsKeys>,
+) -> ConfigResult<Option<Value>> {
+    let Some(tree) = sops_document(bytes, format) else {
+        return Ok(None);
+    };
+    let tree = match keys {
+        Some(keys) => decrypt_document(tree, keys)?,
+        None => decrypt_document(tree, &SopsKeys::from_env()?)?,
+    };
+    Ok(Some(tree))
+}
+
+/// Check whether a file on disk is SOPS-encrypted
+pub async fn is_encrypted_file(path: &Path) -> bool {
+    let Ok(bytes) = tokio::fs::read(path).await else {
+        return false;
+    };
+    let format = crate::loader::detect_format(path).unwrap_or(crate::loader::ConfigFormat::Unknown);
+    sops_document(&bytes, &format).is_some()
+}
+
+/// Parse `bytes` if they look like a SOPS document
+fn sops_document(bytes: &[u8], format: &crate::loader::ConfigFormat) -> Option<Value> {
+    use crate::loader::ConfigFormat;
+
+    let candidate = matches!(
+        format,
+        ConfigFormat::Json | ConfigFormat::Yaml | ConfigFormat::Unknown
+    ) && bytes.windows(4).any(|window| window == b"sops");
+    if !candidate {
+        return None;
+    }
+    crate::loader::load_from_bytes::<Value>(bytes.to_vec(), format)
+        .ok()
+        .filter(is_sops_document)
+}
+
+/// Decrypts the values of a document and hashes them for the MAC
+struct Walker<'a> {
+    key: &'a [u8],
+    unencrypted_suffix: Option<&'a str>,
+    encrypted_suffix: Option<&'a str>,
+    mac_only_encrypted: bool,
+    hash: Sha512,
+}
+
+impl Walker<'_> {
+    /// Visit values in document order; list items share the path of their list
+    fn walk(&mut self, value: &mut Value, path: &mut Vec<String>) -> ConfigResult<()> {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    path.push(key.clone());
+                    self.walk(child, path)?;
+                    path.pop();
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.walk(item, path)?;
+                }
+            }
+            Value::Null => {}
+            leaf => {
+                let encrypted = self.is_encrypted(path)
+                    && leaf.as_str().is_some_and(|text| text.starts_with(PREFIX));
+                if encrypted {
+                    let additional_data = format!("{}:", path.join(":"));
+                    *leaf = decrypt_value(
+                        leaf.as_str().unwrap_or_default(),
+                        self.key,
+                        &additional_data,
+                    )?;
+                }
+                if encrypted || !self.mac_only_encrypted {
+                    self.hash.update(mac_bytes(leaf));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the suffix rules from the metadata to a key path
+    fn is_encrypted(&self, path: &[String]) -> bool {
+        let has_suffix = |suffix: &str| path.iter().any(|key| key.ends_with(suffix));
+        if let Some(suffix) = self.unencrypted_suffix {
+            if has_suffix(suffix) {
+                return false;
+            }
+        }
+        match self.encrypted_suffix {
+            Some(suffix) => has_suffix(suffix),
+            None => true,
+        }
+    }
+}
+
+/// Bytes of a plain value as SOPS feeds them into the MAC
+fn mac_bytes(value: &Value) -> Vec<u8> {
+    match value {
+        Value::Bool(true) => b"True".to_vec(),
+        Value::Bool(false) => b"False".to_vec(),
+        Value::String(text) => text.as_bytes().to_vec(),
+        // Go's `strconv.FormatFloat(v, 'f', -1, 64)`: shortest digits, no
+        // exponent and no `.0` for integral floats, as Rust prints `f64`
+        Value::Number(number) if number.is_f64() => {
+            number.as_f64().unwrap_or_default().to_string().into_bytes()
+        }
+        other => crate::value::scalar_to_string(other).into_bytes(),
+    }
+}
+
+/// Prefix of a value encrypted by SOPS
+const PREFIX: &str = "ENC[AES256_GCM,";
+
+/// Decrypt a single `ENC[AES256_GCM,...]` value, restoring its type
+fn decrypt_value(text: &str, key: &[u8], additional_data: &str) -> ConfigResult<Value> {
+    let invalid =
+        || ConfigError::Encryption(format!("Malformed SOPS value at '{additional_data}'"));
+    let body = text
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+
+    let (mut data, mut iv, mut tag, mut kind) = (None, None, None, "str");
+    for field in body.split(',') {
+        match field.split_once(':').ok_or_else(invalid)? {
+            ("data", value) => data = Some(BASE64.decode(value).map_err(|_| invalid())?),
+            ("iv", value) => iv = Some(BASE64.decode(value).map_err(|_| invalid())?),
+            ("tag", value) => tag = Some(BASE64.decode(value).map_err(|_| invalid())?),
+            ("type", value) => kind = value,
+            _ => {}
+        }
+    }
+    let (mut data, iv, tag) = (
+        data.ok_or_else(invalid)?,
+        iv.filter(|iv| iv.len() == 32).ok_or_else(invalid)?,
+        tag.ok_or_else(invalid)?,
+    );
+    data.extend_from_slice(&tag);
+
+    let cipher = SopsCipher::new_from_slice(key)
+        .map_err(|_| ConfigError::Encryption("Invalid SOPS data key".to_string()))?;
+    let plaintext = cipher
+        .decrypt(
+            iv.as_slice().into(),
+            Payload {
+                msg: &data,
+                aad: additional_data.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            ConfigError::Encryption(format!("Cannot decrypt SOPS value at '{additional_data}'"))
+        })?;
+    let plaintext = String::from_utf8_lossy(&plaintext).into_owned();
+
+    let typed = match kind {
+        "int" => plaintext.parse::<i64>().ok().map(Value::from),
+        "float" => plaintext
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        "bool" => match plaintext.to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => Some(Value::String(plaintext)),
+    };
+    typed.ok_or_else(|| {
+        ConfigError::Encryption(format!("SOPS value at '{additional_data}' is not a {kind}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ConfigFormat;
+    use serde::{Deserialize, Serialize};
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sops");
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Secrets {
+        database: Database,
+        empty: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Database {
+        user: String,
+        password: String,
+        port: u16,
+        ratio: f64,
+        scale: f64,
+        enabled: bool,
+        hosts: Vec<String>,
+        note_unencrypted: String,
+        weight_unencrypted: f64,
+    }
+
+    fn expected() -> Secrets {
+        Secrets {
+            database: Database {
+                user: "app".to_string(),
+                password: "hunter2".to_string(),
+                port: 5432,
+                ratio: 0.75,
+                scale: 1.0,
+                enabled: true,
+                hosts: vec!["db1.internal".to_string(), "db2.internal".to_string()],
+                note_unencrypted: "rotated quarterly".to_string(),
+                weight_unencrypted: 1.0,
+            },
+            empty: String::new(),
+        }
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(FIXTURES).join(name)
+    }
+
+    fn age_keys() -> SopsKeys {
+        SopsKeys::new()
+            .age_key_file(fixture("age-keys.txt"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decrypt_age_yaml() {
+        let bytes = std::fs::read(fixture("secrets.age.yaml")).unwrap();
+        let tree = decrypt_content(&bytes, &ConfigFormat::Yaml, Some(&age_keys()))
+            .unwrap()
+            .unwrap();
+        assert!(tree.get("sops").is_none());
+
+        let secrets: Secrets = serde_json::from_value(tree).unwrap();
+        assert_eq!(secrets, expected());
+    }
+
+    #[test]
+    fn test_decrypt_pgp_json() {
+        assert!(
+            Command::new("gpg").arg("--version").output().is_ok(),
+            "the PGP test needs gpg on the PATH"
+        );
+        let keys = SopsKeys::new().pgp_key_file(fixture("pgp-key.asc"));
+        let bytes = std::fs::read(fixture("secrets.pgp.json")).unwrap();
+        let tree = decrypt_content(&bytes, &ConfigFormat::Json, Some(&keys))
+            .unwrap()
+            .unwrap();
+
+        let secrets: Secrets = serde_json::from_value(tree).unwrap();
+        assert_eq!(secrets, expected());
+    }
+
+    #[test]
+    fn test_tampering_and_wrong_keys_fail() {
+        let content = std::fs::read_to_string(fixture("secrets.age.yaml")).unwrap();
+
+        // Unencrypted values are covered by the MAC
+        let tampered = content.replace("rotated quarterly", "rotated never");
+        let err = decrypt_content(tampered.as_bytes(), &ConfigFormat::Yaml, Some(&age_keys()))
+            .unwrap_err();
+        assert!(err.to_string().contains("MAC mismatch"), "{err}");
+
+        let other_key = age::x25519::Identity::generate();
+        let keys = SopsKeys::new()
+            .age_key(age::secrecy::ExposeSecret::expose_secret(
+                &other_key.to_string(),
+            ))
+            .unwrap();
+        let err =
+            decrypt_content(content.as_bytes(), &ConfigFormat::Yaml, Some(&keys)).unwrap_err();
+        assert!(err.to_string().contains("SOPS data key"), "{err}");
+
+        // Plain documents are left alone
+        assert!(decrypt_content(b"sops: plain\n", &ConfigFormat::Yaml, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_config_loads_sops_and_refuses_plain_save() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("secrets.yaml");
+        tokio::fs::copy(fixture("secrets.age.yaml"), &path)
+            .await
+            .unwrap();
+
+        let config = crate::ConfigBuilder::new()
+            .file(&path)
+            .sops_keys(age_keys())
+            .build::<Secrets>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().database.password, "hunter2");
+
+        assert!(config.save().await.is_err());
+        let unchanged = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(!unchanged.contains("hunter2"));
+    }
+}
@@ -0,0 +1,501 @@
+//! Human-readable durations, byte sizes and percentages
+//!
+//! ```yaml
+//! timeout: 1m30s
+//! max_body: 10MiB
+//! ratio: 75%
+//! ```
+//!
+//! [`ConfigDuration`], [`ByteSize`] and [`Percent`] deserialize from such
+//! strings in every format and serialize back to them. Plain numbers are
+//! read as seconds, bytes and percent respectively.
+//!
+//! ```rust
+//! use rusty_config::units::{ByteSize, ConfigDuration, Percent};
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Limits {
+//!     timeout: ConfigDuration,
+//!     max_body: ByteSize,
+//!     ratio: Percent,
+//! }
+//!
+//! let limits: Limits =
+//!     serde_json::from_str(r#"{"timeout": "1m30s", "max_body": "10MiB", "ratio": "75%"}"#)
+//!         .unwrap();
+//! assert_eq!(limits.timeout.as_duration().as_secs(), 90);
+//! assert_eq!(limits.max_body.as_u64(), 10 * 1024 * 1024);
+//! assert_eq!(limits.ratio.as_ratio(), 0.75);
+//! ```
+
+use crate::{ConfigError, ConfigResult};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Split `"1.5GiB"` into the number and the unit
+fn split_number(text: &str) -> ConfigResult<(f64, &str)> {
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(end);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| ConfigError::InvalidFormat(format!("Expected a number in '{text}'")))?;
+    Ok((number, unit.trim()))
+}
+
+/// Deserialize from a string or a plain number
+macro_rules! deserialize_with_number {
+    ($type:ident, $expecting:literal, $from_number:expr) => {
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct UnitVisitor;
+
+                impl Visitor<'_> for UnitVisitor {
+                    type Value = $type;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: de::Error>(self, text: &str) -> Result<$type, E> {
+                        text.parse().map_err(E::custom)
+                    }
+
+                    fn visit_u64<E: de::Error>(self, number: u64) -> Result<$type, E> {
+                        self.visit_f64(number as f64)
+                    }
+
+                    fn visit_i64<E: de::Error>(self, number: i64) -> Result<$type, E> {
+                        self.visit_f64(number as f64)
+                    }
+
+                    fn visit_f64<E: de::Error>(self, number: f64) -> Result<$type, E> {
+                        if !number.is_finite() || number < 0.0 {
+                            return Err(E::custom(format!("{number} is not a valid {}", $expecting)));
+                        }
+                        $from_number(number).map_err(E::custom)
+                    }
+                }
+
+                deserializer.deserialize_any(UnitVisitor)
+            }
+        }
+
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        #[cfg(feature = "schema")]
+        impl schemars::JsonSchema for $type {
+            fn schema_name() -> std::borrow::Cow<'static, str> {
+                stringify!($type).into()
+            }
+
+            fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                schemars::json_schema!({
+                    "type": ["string", "number"],
+                    "description": $expecting,
+                })
+            }
+        }
+    };
+}
+
+/// A duration like `30s`, `1h30m` or `250ms`
+///
+/// Units are `ns`, `us`, `ms`, `s`, `m`, `h` and `d`; components can be
+/// combined and have fractions (`1.5h`). Numbers, and strings without a
+/// unit, are read as seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConfigDuration(Duration);
+
+impl ConfigDuration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(Duration::from_millis(millis))
+    }
+
+    pub const fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Duration of `secs` seconds, `text` is the input for the error message
+    fn from_secs_checked(secs: f64, text: &str) -> ConfigResult<Self> {
+        Duration::try_from_secs_f64(secs)
+            .map(Self)
+            .map_err(|_| ConfigError::InvalidFormat(format!("Duration '{text}' is too long")))
+    }
+}
+
+impl From<Duration> for ConfigDuration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl From<ConfigDuration> for Duration {
+    fn from(duration: ConfigDuration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for ConfigDuration {
+    type Err = ConfigError;
+
+    fn from_str(text: &str) -> ConfigResult<Self> {
+        let text = text.trim();
+        if let Ok((secs, "")) = split_number(text) {
+            // Flat formats and env overrides write plain numbers as strings
+            return Self::from_secs_checked(secs, text);
+        }
+        if text.is_empty() {
+            return Err(ConfigError::InvalidFormat("Empty duration".to_string()));
+        }
+
+        let mut nanos = 0f64;
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (number, tail) = split_number(rest)?;
+            let unit_end = tail
+                .find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+                .unwrap_or(tail.len());
+            let (unit, tail) = tail.split_at(unit_end);
+            let factor = match unit {
+                "ns" => 1.0,
+                "us" | "µs" => 1e3,
+                "ms" => 1e6,
+                "s" | "sec" => 1e9,
+                "m" | "min" => 60e9,
+                "h" => 3600e9,
+                "d" => 86400e9,
+                "" => {
+                    return Err(ConfigError::InvalidFormat(format!(
+                        "Missing unit in duration '{text}', e.g. 30s"
+                    )))
+                }
+                _ => {
+                    return Err(ConfigError::InvalidFormat(format!(
+                        "Unknown duration unit '{unit}' in '{text}'"
+                    )))
+                }
+            };
+            nanos += number * factor;
+            rest = tail.trim_start();
+        }
+
+        if nanos.round() >= u64::MAX as f64 {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Duration '{text}' is too long"
+            )));
+        }
+        Ok(Self(Duration::from_nanos(nanos.round() as u64)))
+    }
+}
+
+impl fmt::Display for ConfigDuration {
+    /// Writes the shortest exact form, e.g. `1h30m` or `1s500ms`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(&str, u128); 7] = [
+            ("d", 86_400_000_000_000),
+            ("h", 3_600_000_000_000),
+            ("m", 60_000_000_000),
+            ("s", 1_000_000_000),
+            ("ms", 1_000_000),
+            ("us", 1_000),
+            ("ns", 1),
+        ];
+        let mut nanos = self.0.as_nanos();
+        if nanos == 0 {
+            return f.write_str("0s");
+        }
+        for (unit, size) in UNITS {
+            if nanos >= size {
+                write!(f, "{}{unit}", nanos / size)?;
+                nanos %= size;
+            }
+        }
+        Ok(())
+    }
+}
+
+deserialize_with_number!(
+    ConfigDuration,
+    "a duration like 30s or 1h30m",
+    |secs: f64| { ConfigDuration::from_secs_checked(secs, &secs.to_string()) }
+);
+
+/// A number of bytes like `512B`, `64KB` or `10MiB`
+///
+/// Decimal units (`KB`, `MB`, `GB`, `TB`, `PB`) are powers of 1000, binary
+/// units (`KiB` ... `PiB`) powers of 1024. Units are case-insensitive and
+/// `K`, `M`, ... are short for the decimal ones. Numbers are read as bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(u64);
+
+const KIB: u64 = 1024;
+const BYTE_UNITS: [(&str, u64); 10] = [
+    ("PiB", KIB.pow(5)),
+    ("PB", 1000u64.pow(5)),
+    ("TiB", KIB.pow(4)),
+    ("TB", 1000u64.pow(4)),
+    ("GiB", KIB.pow(3)),
+    ("GB", 1000u64.pow(3)),
+    ("MiB", KIB.pow(2)),
+    ("MB", 1000u64.pow(2)),
+    ("KiB", KIB),
+    ("KB", 1000),
+];
+
+impl ByteSize {
+    pub const fn b(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn kib(kib: u64) -> Self {
+        Self(kib * KIB)
+    }
+
+    pub const fn mib(mib: u64) -> Self {
+        Self(mib * KIB.pow(2))
+    }
+
+    pub const fn gib(gib: u64) -> Self {
+        Self(gib * KIB.pow(3))
+    }
+
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// Size of `bytes` rounded to whole bytes, `text` is the input for the
+    /// error message
+    fn from_bytes_checked(bytes: f64, text: &str) -> ConfigResult<Self> {
+        if bytes.round() >= u64::MAX as f64 {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Size '{text}' is too large"
+            )));
+        }
+        Ok(Self(bytes.round() as u64))
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ConfigError;
+
+    fn from_str(text: &str) -> ConfigResult<Self> {
+        let (number, unit) = split_number(text.trim())?;
+        let factor = match unit.to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" => 1000,
+            "m" | "mb" => 1000u64.pow(2),
+            "g" | "gb" => 1000u64.pow(3),
+            "t" | "tb" => 1000u64.pow(4),
+            "p" | "pb" => 1000u64.pow(5),
+            "kib" => KIB,
+            "mib" => KIB.pow(2),
+            "gib" => KIB.pow(3),
+            "tib" => KIB.pow(4),
+            "pib" => KIB.pow(5),
+            _ => {
+                return Err(ConfigError::InvalidFormat(format!(
+                    "Unknown size unit '{unit}' in '{text}'"
+                )))
+            }
+        };
+        Self::from_bytes_checked(number * factor as f64, text)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    /// Writes the largest unit that represents the size exactly
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = BYTE_UNITS
+            .iter()
+            .find(|(_, size)| self.0 >= *size && self.0 / size * size == self.0);
+        match unit {
+            Some((unit, size)) => write!(f, "{}{unit}", self.0 / size),
+            None => write!(f, "{}B", self.0),
+        }
+    }
+}
+
+deserialize_with_number!(ByteSize, "a size like 512B or 10MiB", |bytes: f64| {
+    ByteSize::from_bytes_checked(bytes, &bytes.to_string())
+});
+
+/// A percentage like `75%` or `12.5%`
+///
+/// Numbers are read as percent, so `75`, `"75"` and `"75%"` are the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Percent(f64);
+
+impl Percent {
+    pub const fn new(percent: f64) -> Self {
+        Self(percent)
+    }
+
+    /// The percentage, e.g. `75.0` for `75%`
+    pub const fn value(&self) -> f64 {
+        self.0
+    }
+
+    /// The fraction, e.g. `0.75` for `75%`
+    pub fn as_ratio(&self) -> f64 {
+        self.0 / 100.0
+    }
+}
+
+impl FromStr for Percent {
+    type Err = ConfigError;
+
+    fn from_str(text: &str) -> ConfigResult<Self> {
+        let trimmed = text.trim();
+        let number = trimmed.strip_suffix('%').unwrap_or(trimmed).trim_end();
+        let parsed = split_number(number).map_err(|_| {
+            ConfigError::InvalidFormat(format!("Expected a percentage like 75%, got '{text}'"))
+        })?;
+        match parsed {
+            (percent, "") => Ok(Self(percent)),
+            _ => Err(ConfigError::InvalidFormat(format!(
+                "Invalid percentage '{text}'"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+deserialize_with_number!(Percent, "a percentage like 75%", |percent| {
+    ConfigResult::Ok(Percent(percent))
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_round_trip() {
+        for (text, duration, display) in [
+            ("30s", Duration::from_secs(30), "30s"),
+            ("1h30m", Duration::from_secs(5400), "1h30m"),
+            ("1.5h", Duration::from_secs(5400), "1h30m"),
+            ("1m 500ms", Duration::from_millis(60_500), "1m500ms"),
+            ("2d", Duration::from_secs(172_800), "2d"),
+            ("0", Duration::ZERO, "0s"),
+            ("30", Duration::from_secs(30), "30s"),
+            ("0.5", Duration::from_millis(500), "500ms"),
+        ] {
+            let parsed: ConfigDuration = text.parse().unwrap();
+            assert_eq!(parsed.as_duration(), duration, "{text}");
+            assert_eq!(parsed.to_string(), display);
+        }
+        assert!("30 years".parse::<ConfigDuration>().is_err());
+        assert!("".parse::<ConfigDuration>().is_err());
+
+        let text = "1".repeat(30).parse::<ConfigDuration>().unwrap_err();
+        let number = serde_json::from_str::<ConfigDuration>("1e30").unwrap_err();
+        assert!(text.to_string().contains("too long"), "{text}");
+        assert!(number.to_string().contains("too long"), "{number}");
+    }
+
+    #[test]
+    fn test_byte_size_round_trip() {
+        for (text, bytes, display) in [
+            ("10MiB", 10 * 1024 * 1024, "10MiB"),
+            ("64kb", 64_000, "64KB"),
+            ("1.5GiB", 1536 * 1024 * 1024, "1536MiB"),
+            ("1000", 1000, "1KB"),
+            ("1234 B", 1234, "1234B"),
+        ] {
+            let parsed: ByteSize = text.parse().unwrap();
+            assert_eq!(parsed.as_u64(), bytes, "{text}");
+            assert_eq!(parsed.to_string(), display);
+        }
+        assert!("10 parsecs".parse::<ByteSize>().is_err());
+
+        let text = "20000000PB".parse::<ByteSize>().unwrap_err();
+        let number = serde_json::from_str::<ByteSize>("1e20").unwrap_err();
+        assert!(text.to_string().contains("too large"), "{text}");
+        assert!(number.to_string().contains("too large"), "{number}");
+    }
+
+    #[cfg(all(feature = "yaml", feature = "toml", feature = "json", feature = "ini"))]
+    #[test]
+    fn test_units_in_formats() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Limits {
+            timeout: ConfigDuration,
+            max_body: ByteSize,
+            ratio: Percent,
+        }
+        let expected = Limits {
+            timeout: ConfigDuration::from_secs(30),
+            max_body: ByteSize::mib(10),
+            ratio: Percent::new(75.0),
+        };
+
+        let yaml = "timeout: 30s\nmax_body: 10MiB\nratio: 75%\n";
+        assert_eq!(serde_yaml::from_str::<Limits>(yaml).unwrap(), expected);
+        assert_eq!(serde_yaml::to_string(&expected).unwrap(), yaml);
+
+        let toml = "timeout = 30\nmax_body = 10485760\nratio = 75\n";
+        assert_eq!(toml::from_str::<Limits>(toml).unwrap(), expected);
+
+        let ini = "timeout = 30\nmax_body = 10485760\nratio = 75\n";
+        let from_ini: Limits = crate::loader::load_from_bytes(
+            ini.as_bytes().to_vec(),
+            &crate::loader::ConfigFormat::Ini,
+        )
+        .unwrap();
+        assert_eq!(from_ini, expected);
+
+        let json = r#"{"timeout":"30s","max_body":"10MiB","ratio":"75%"}"#;
+        assert_eq!(serde_json::to_string(&expected).unwrap(), json);
+        assert!(
+            serde_json::from_str::<Limits>(r#"{"timeout":-1,"max_body":1,"ratio":"1%"}"#).is_err()
+        );
+    }
+
+    #[cfg(feature = "properties")]
+    #[test]
+    fn test_unitless_strings_from_properties() {
+        #[derive(Debug, Deserialize)]
+        struct Retry {
+            backoff: ConfigDuration,
+            jitter: Percent,
+        }
+        let retry: Retry = crate::loader::load_from_bytes(
+            b"backoff=1.5\njitter=12.5\n".to_vec(),
+            &crate::loader::ConfigFormat::Properties,
+        )
+        .unwrap();
+        assert_eq!(retry.backoff.as_duration(), Duration::from_millis(1500));
+        assert_eq!(retry.jitter.value(), 12.5);
+        assert!("abc".parse::<Percent>().is_err());
+    }
+}
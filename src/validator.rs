@@ -1,20 +1,58 @@
+use crate::checks::AsyncChecks;
+use crate::i18n::{self, MessageCatalog};
+use crate::units::{ByteSize, ConfigDuration, Percent};
 use crate::{ConfigError, ConfigResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
 
 /// Trait for validatable configurations
+///
+/// [`validate`](Self::validate) stops at the first error. To record every
+/// failure in a [`ValidationContext`] instead, override
+/// [`validate_into`](Self::validate_into) and forward `validate` to
+/// [`validate`](crate::validator::validate()), which collects them.
+/// Forwarding without overriding `validate_into` would recurse forever, so
+/// that call fails with an error instead.
 #[async_trait]
 pub trait Validatable {
     /// Validate the configuration
     async fn validate(&self) -> ConfigResult<()>;
+
+    /// Record every validation failure in `ctx`
+    async fn validate_into(&self, ctx: &mut ValidationContext)
+    where
+        Self: Sync,
+    {
+        let current = (
+            self as *const Self as *const () as usize,
+            std::any::type_name::<Self>(),
+        );
+        if let Err(err) = DEFAULT_VALIDATE_INTO.scope(current, self.validate()).await {
+            ctx.record(err);
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Address and type of the value whose default `validate_into` is running
+    static DEFAULT_VALIDATE_INTO: (usize, &'static str);
 }
 
 /// Standard validation error
+///
+/// Errors with [`params`](Self::params) are templated:
+/// [`Display`](std::fmt::Display) renders them from the current [`i18n`]
+/// catalog, while `message` holds the English text.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
     pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<BTreeMap<String, String>>,
 }
 
 impl ValidationError {
@@ -23,6 +61,28 @@ impl ValidationError {
             field: field.into(),
             message: message.into(),
             code: "VALIDATION_ERROR".to_string(),
+            params: None,
+        }
+    }
+
+    /// Create an error whose message is rendered from the template for `code`
+    pub fn templated(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        params: &[(&str, String)],
+    ) -> Self {
+        let code = code.into();
+        let params: BTreeMap<_, _> = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        let message = i18n::template(&i18n::EN, &code, &params)
+            .map_or_else(|| code.clone(), |template| i18n::render(template, &params));
+        Self {
+            field: field.into(),
+            message,
+            code,
+            params: Some(params),
         }
     }
 
@@ -30,6 +90,44 @@ impl ValidationError {
         self.code = code.into();
         self
     }
+
+    /// Template parameters, `None` for errors with a fixed message
+    pub fn params(&self) -> Option<&BTreeMap<String, String>> {
+        self.params.as_ref()
+    }
+
+    /// Add a template parameter, making the error templated
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params
+            .get_or_insert_with(BTreeMap::new)
+            .insert(name.into(), value.into());
+        self
+    }
+
+    /// The message in the language of `catalog`
+    ///
+    /// Falls back to `message` for errors without parameters or without a
+    /// template in the catalog.
+    pub fn localized(&self, catalog: &dyn MessageCatalog) -> String {
+        self.params
+            .as_ref()
+            .and_then(|params| {
+                let template = i18n::template(catalog, &self.code, params)?;
+                Some(i18n::render(template, params))
+            })
+            .unwrap_or_else(|| self.message.clone())
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = self.localized(&*i18n::catalog());
+        if self.field.is_empty() {
+            write!(f, "{} [{}]", message, self.code)
+        } else {
+            write!(f, "{}: {} [{}]", self.field, message, self.code)
+        }
+    }
 }
 
 /// Validation result
@@ -71,26 +169,261 @@ impl Default for ValidationResult {
     }
 }
 
+/// One step of a [`ValidationContext`] path
+#[derive(Debug, Clone)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// Collects every validation failure together with its path
+///
+/// Paths are built from the nesting of [`field`](Self::field),
+/// [`each`](Self::each) and [`nested`](Self::nested) calls, e.g.
+/// `servers[2].port`:
+///
+/// ```rust
+/// use rusty_config::validator::{TypeValidator, ValidationContext};
+///
+/// let ports = [8080, 443, 0];
+/// let mut ctx = ValidationContext::new();
+/// ctx.each("servers", &ports, |ctx, port| {
+///     ctx.check("port", TypeValidator::port(*port, "port"));
+/// });
+/// let result = ctx.into_result();
+/// assert_eq!(result.errors[0].field, "servers[2].port");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ValidationContext {
+    path: Vec<PathSegment>,
+    result: ValidationResult,
+}
+
+impl ValidationContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current path, empty at the root
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+
+    /// Path of `field` below the current path
+    fn path_of(&self, field: &str) -> String {
+        let path = self.path();
+        match (path.is_empty(), field.is_empty()) {
+            (true, _) => field.to_string(),
+            (false, true) => path,
+            (false, false) => format!("{path}.{field}"),
+        }
+    }
+
+    /// Record the outcome of a check on `field`, e.g. a [`TypeValidator`] call
+    ///
+    /// Errors are recorded at `field`, keeping their message and code.
+    pub fn check(&mut self, field: &str, result: ConfigResult<()>) -> &mut Self {
+        match result {
+            Ok(()) => {}
+            Err(ConfigError::ValidationErrors(errors)) => {
+                for mut error in errors {
+                    error.field = field.to_string();
+                    self.add_error(error);
+                }
+            }
+            Err(err) => {
+                self.field(field, |ctx| ctx.record(err));
+            }
+        }
+        self
+    }
+
+    /// Record an error on `field`
+    pub fn error(&mut self, field: &str, message: impl Into<String>) -> &mut Self {
+        self.result
+            .add_error(ValidationError::new(self.path_of(field), message));
+        self
+    }
+
+    /// Record an error, its field taken relative to the current path
+    pub fn add_error(&mut self, mut error: ValidationError) -> &mut Self {
+        error.field = self.path_of(&error.field);
+        self.result.add_error(error);
+        self
+    }
+
+    /// Record a warning on `field`
+    pub fn warning(&mut self, field: &str, message: impl Into<String>) -> &mut Self {
+        self.result
+            .add_warning(ValidationError::new(self.path_of(field), message));
+        self
+    }
+
+    /// Record an error returned by a validation at the current path
+    ///
+    /// [`ConfigError::ValidationErrors`] are added one by one, with their
+    /// fields prefixed by the current path.
+    pub fn record(&mut self, err: ConfigError) {
+        match err {
+            ConfigError::Validation(message) => self.error("", message),
+            ConfigError::ValidationErrors(errors) => {
+                for error in errors {
+                    self.add_error(error);
+                }
+                self
+            }
+            other => self.error("", other.to_string()),
+        };
+    }
+
+    /// Run checks below `name`
+    pub fn field<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        if name.is_empty() {
+            f(self);
+            return self;
+        }
+        self.path.push(PathSegment::Field(name.to_string()));
+        f(self);
+        self.path.pop();
+        self
+    }
+
+    /// Run checks for every item of the list `name`, with indexed paths
+    pub fn each<'a, I, V, F>(&mut self, name: &str, items: I, mut f: F) -> &mut Self
+    where
+        I: IntoIterator<Item = &'a V>,
+        V: 'a + ?Sized,
+        F: FnMut(&mut Self, &'a V),
+    {
+        self.field(name, |ctx| {
+            for (index, item) in items.into_iter().enumerate() {
+                ctx.path.push(PathSegment::Index(index));
+                f(ctx, item);
+                ctx.path.pop();
+            }
+        })
+    }
+
+    /// Validate a nested struct below `name`
+    pub async fn nested<V>(&mut self, name: &str, value: &V) -> &mut Self
+    where
+        V: Validatable + Sync + ?Sized,
+    {
+        self.path.push(PathSegment::Field(name.to_string()));
+        value.validate_into(self).await;
+        self.path.pop();
+        self
+    }
+
+    /// Validate every item of the list `name`, with indexed paths
+    pub async fn nested_each<V>(&mut self, name: &str, items: &[V]) -> &mut Self
+    where
+        V: Validatable + Sync,
+    {
+        self.path.push(PathSegment::Field(name.to_string()));
+        for (index, item) in items.iter().enumerate() {
+            self.path.push(PathSegment::Index(index));
+            item.validate_into(self).await;
+            self.path.pop();
+        }
+        self.path.pop();
+        self
+    }
+
+    /// Whether no error was recorded so far
+    pub fn is_valid(&self) -> bool {
+        self.result.is_valid
+    }
+
+    /// The failures recorded so far
+    pub fn result(&self) -> &ValidationResult {
+        &self.result
+    }
+
+    pub fn into_result(self) -> ValidationResult {
+        self.result
+    }
+
+    /// `Ok` if no error was recorded, otherwise all errors at once
+    pub fn finish(self) -> ConfigResult<()> {
+        if self.result.is_valid {
+            Ok(())
+        } else {
+            Err(ConfigError::ValidationErrors(self.result.errors))
+        }
+    }
+}
+
 /// Validate a configuration
+///
+/// Fails with [`ConfigError::ValidationErrors`] listing every failure.
 pub async fn validate<T>(config: &T) -> ConfigResult<()>
 where
-    T: Validatable,
+    T: Validatable + Sync,
 {
-    config.validate().await
+    let current = (
+        config as *const T as *const () as usize,
+        std::any::type_name::<T>(),
+    );
+    if DEFAULT_VALIDATE_INTO
+        .try_with(|running| *running == current)
+        .unwrap_or(false)
+    {
+        return Err(ConfigError::Validation(format!(
+            "{} forwards validate to validator::validate without implementing validate_into",
+            current.1
+        )));
+    }
+
+    let mut ctx = ValidationContext::new();
+    config.validate_into(&mut ctx).await;
+    ctx.finish()
 }
 
 /// Validate a configuration and return detailed results
+///
+/// Runs [`DetailedValidatable::validate_detailed`] followed by the
+/// configuration's [`async_checks`](DetailedValidatable::async_checks).
 pub async fn validate_detailed<T>(config: &T) -> ConfigResult<ValidationResult>
 where
-    T: Validatable + DetailedValidatable,
+    T: Validatable + DetailedValidatable + Sync,
 {
-    config.validate_detailed().await
+    let mut result = config.validate_detailed().await?;
+    result.merge(config.async_checks().run().await);
+    Ok(result)
 }
 
 /// Extended validation with detailed results
 #[async_trait]
 pub trait DetailedValidatable: Validatable {
-    async fn validate_detailed(&self) -> ConfigResult<ValidationResult>;
+    /// Collect every failure of [`Validatable::validate_into`]
+    async fn validate_detailed(&self) -> ConfigResult<ValidationResult>
+    where
+        Self: Sync,
+    {
+        let mut ctx = ValidationContext::new();
+        self.validate_into(&mut ctx).await;
+        Ok(ctx.into_result())
+    }
+
+    /// Checks of local resources, run concurrently by [`validate_detailed`]
+    fn async_checks(&self) -> AsyncChecks<'_> {
+        AsyncChecks::new()
+    }
 }
 
 /// Type alias for a validation function
@@ -118,16 +451,103 @@ impl<T> ValidationRule<T> {
     }
 }
 
+/// Stable codes of the errors reported by [`TypeValidator`] and
+/// [`ResourceValidator`](crate::checks::ResourceValidator)
+pub mod codes {
+    pub const NOT_EMPTY: &str = "not_empty";
+    pub const LENGTH: &str = "length";
+    pub const RANGE: &str = "range";
+    pub const URL: &str = "url";
+    pub const URL_SCHEME: &str = "url_scheme";
+    pub const EMAIL: &str = "email";
+    pub const PORT: &str = "port";
+    pub const HOSTNAME: &str = "hostname";
+    pub const IP: &str = "ip";
+    pub const IPV4: &str = "ipv4";
+    pub const IPV6: &str = "ipv6";
+    pub const CIDR: &str = "cidr";
+    pub const SOCKET_ADDR: &str = "socket_addr";
+    pub const PATTERN: &str = "pattern";
+    pub const SEMVER: &str = "semver";
+    pub const SEMVER_REQUIREMENT: &str = "semver_requirement";
+    pub const PATH_NOT_FOUND: &str = "path_not_found";
+    pub const NOT_A_FILE: &str = "not_a_file";
+    pub const NOT_A_DIRECTORY: &str = "not_a_directory";
+    pub const PERMISSION_DENIED: &str = "permission_denied";
+    pub const ONE_OF: &str = "one_of";
+    pub const UNIQUE: &str = "unique";
+    pub const DURATION: &str = "duration";
+    pub const BYTE_SIZE: &str = "byte_size";
+    pub const PERCENT: &str = "percent";
+    pub const DEPRECATED_KEY: &str = "deprecated_key";
+    pub const UNKNOWN_KEY: &str = "unknown_key";
+    pub const MIGRATION: &str = "migration";
+    pub const TIMEOUT: &str = "timeout";
+    pub const NOT_A_SOCKET: &str = "not_a_socket";
+    pub const PORT_UNAVAILABLE: &str = "port_unavailable";
+    pub const INVALID_CERTIFICATE: &str = "invalid_certificate";
+    pub const CERTIFICATE_EXPIRED: &str = "certificate_expired";
+    pub const REQUIRED: &str = "required";
+    pub const EQUALS: &str = "equals";
+    pub const ANY: &str = "any";
+    pub const REQUIRES: &str = "requires";
+    pub const MUTUALLY_EXCLUSIVE: &str = "mutually_exclusive";
+    pub const EXACTLY_ONE: &str = "exactly_one";
+    pub const COMPARE: &str = "compare";
+}
+
+/// A failed check of `field` with a stable code and template parameters
+pub(crate) fn invalid(field: &str, code: &str, params: &[(&str, String)]) -> ConfigError {
+    ConfigError::ValidationErrors(vec![ValidationError::templated(field, code, params)])
+}
+
+/// Access a path must allow, see [`TypeValidator::file`]
+///
+/// Read and write access are tried for real: files are opened, directories
+/// are listed and get a probe file created and removed again. Execute access
+/// is read from the mode bits on Unix, so it does not account for the owner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Access {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Access {
+    /// Only existence is checked
+    pub const ANY: Access = Access {
+        read: false,
+        write: false,
+        execute: false,
+    };
+    pub const READ: Access = Access {
+        read: true,
+        write: false,
+        execute: false,
+    };
+    pub const READ_WRITE: Access = Access {
+        read: true,
+        write: true,
+        execute: false,
+    };
+    pub const EXECUTE: Access = Access {
+        read: false,
+        write: false,
+        execute: true,
+    };
+}
+
 /// Validator for various data types
+///
+/// Every check fails with [`ConfigError::ValidationErrors`] holding one
+/// [`ValidationError`] for `field_name`, with a code from [`codes`].
 pub struct TypeValidator;
 
 impl TypeValidator {
     /// Validate that a string is not empty
     pub fn not_empty(value: &str, field_name: &str) -> ConfigResult<()> {
         if value.trim().is_empty() {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must not be empty"
-            )));
+            return Err(invalid(field_name, codes::NOT_EMPTY, &[]));
         }
         Ok(())
     }
@@ -136,9 +556,15 @@ impl TypeValidator {
     pub fn length(value: &str, min: usize, max: usize, field_name: &str) -> ConfigResult<()> {
         let len = value.len();
         if len < min || len > max {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must be between {min} and {max} characters long (currently: {len})"
-            )));
+            return Err(invalid(
+                field_name,
+                codes::LENGTH,
+                &[
+                    ("min", min.to_string()),
+                    ("max", max.to_string()),
+                    ("actual", len.to_string()),
+                ],
+            ));
         }
         Ok(())
     }
@@ -149,29 +575,62 @@ impl TypeValidator {
         T: PartialOrd + std::fmt::Display,
     {
         if value < min || value > max {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must be between {min} and {max} (currently: {value})"
-            )));
+            return Err(invalid(
+                field_name,
+                codes::RANGE,
+                &[
+                    ("min", min.to_string()),
+                    ("max", max.to_string()),
+                    ("actual", value.to_string()),
+                ],
+            ));
         }
         Ok(())
     }
 
-    /// Validate a URL
+    /// Validate an `http` or `https` URL, see [`url_with_schemes`](Self::url_with_schemes)
     pub fn url(value: &str, field_name: &str) -> ConfigResult<()> {
-        if !value.starts_with("http://") && !value.starts_with("https://") {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must be a valid URL (start with http:// or https://)"
-            )));
+        Self::url_with_schemes(value, &["http", "https"], field_name)
+    }
+
+    /// Validate an RFC 3986 URL whose scheme is one of `schemes`
+    ///
+    /// URLs with an authority (`scheme://host...`) need a valid hostname or
+    /// IP address, except for `file` URLs. Schemes compare case-insensitively.
+    pub fn url_with_schemes(value: &str, schemes: &[&str], field_name: &str) -> ConfigResult<()> {
+        let scheme = parse_url(value)
+            .map_err(|reason| invalid(field_name, codes::URL, &[("reason", reason.to_string())]))?;
+        if !schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        {
+            return Err(invalid(
+                field_name,
+                codes::URL_SCHEME,
+                &[
+                    ("scheme", scheme.to_string()),
+                    ("allowed", format!("{schemes:?}")),
+                ],
+            ));
         }
         Ok(())
     }
 
-    /// Validate an email address (simple validation)
+    /// Validate an email address of the form `local@domain.tld`
     pub fn email(value: &str, field_name: &str) -> ConfigResult<()> {
-        if !value.contains('@') || !value.contains('.') {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must be a valid email address"
-            )));
+        let valid = value.rsplit_once('@').is_some_and(|(local, domain)| {
+            let atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c);
+            !local.is_empty()
+                && local.len() <= 64
+                && local.chars().all(atext)
+                && !local.starts_with('.')
+                && !local.ends_with('.')
+                && !local.contains("..")
+                && domain.contains('.')
+                && is_hostname(domain)
+        });
+        if !valid {
+            return Err(invalid(field_name, codes::EMAIL, &[]));
         }
         Ok(())
     }
@@ -179,14 +638,398 @@ impl TypeValidator {
     /// Validate that a port is in the valid range
     pub fn port(value: u16, field_name: &str) -> ConfigResult<()> {
         if value == 0 {
-            return Err(ConfigError::Validation(format!(
-                "Field '{field_name}' must be a valid port between 1 and 65535 (currently: {value})"
-            )));
+            return Err(invalid(
+                field_name,
+                codes::PORT,
+                &[("actual", value.to_string())],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate a hostname as defined by RFC 1123
+    pub fn hostname(value: &str, field_name: &str) -> ConfigResult<()> {
+        if !is_hostname(value) {
+            return Err(invalid(field_name, codes::HOSTNAME, &[]));
+        }
+        Ok(())
+    }
+
+    /// Validate an IPv4 or IPv6 address
+    pub fn ip(value: &str, field_name: &str) -> ConfigResult<()> {
+        value
+            .parse::<std::net::IpAddr>()
+            .map(drop)
+            .map_err(|_| invalid(field_name, codes::IP, &[]))
+    }
+
+    /// Validate an IPv4 address
+    pub fn ipv4(value: &str, field_name: &str) -> ConfigResult<()> {
+        value
+            .parse::<std::net::Ipv4Addr>()
+            .map(drop)
+            .map_err(|_| invalid(field_name, codes::IPV4, &[]))
+    }
+
+    /// Validate an IPv6 address
+    pub fn ipv6(value: &str, field_name: &str) -> ConfigResult<()> {
+        value
+            .parse::<std::net::Ipv6Addr>()
+            .map(drop)
+            .map_err(|_| invalid(field_name, codes::IPV6, &[]))
+    }
+
+    /// Validate a network in CIDR notation, e.g. `10.0.0.0/8` or `fd00::/8`
+    pub fn cidr(value: &str, field_name: &str) -> ConfigResult<()> {
+        let valid = value.split_once('/').is_some_and(|(address, prefix)| {
+            let max_prefix = match address.parse::<std::net::IpAddr>() {
+                Ok(std::net::IpAddr::V4(_)) => 32,
+                Ok(std::net::IpAddr::V6(_)) => 128,
+                Err(_) => return false,
+            };
+            prefix.bytes().all(|b| b.is_ascii_digit())
+                && prefix
+                    .parse::<u8>()
+                    .is_ok_and(|prefix| prefix <= max_prefix)
+        });
+        if !valid {
+            return Err(invalid(field_name, codes::CIDR, &[]));
+        }
+        Ok(())
+    }
+
+    /// Validate a socket address, e.g. `127.0.0.1:8080` or `[::1]:8080`
+    pub fn socket_addr(value: &str, field_name: &str) -> ConfigResult<()> {
+        value
+            .parse::<std::net::SocketAddr>()
+            .map(drop)
+            .map_err(|_| invalid(field_name, codes::SOCKET_ADDR, &[]))
+    }
+
+    /// Validate that a string matches a regular expression
+    ///
+    /// Anchor the pattern with `^...$` to match the whole value.
+    #[cfg(feature = "validation")]
+    pub fn pattern(value: &str, pattern: &regex::Regex, field_name: &str) -> ConfigResult<()> {
+        if !pattern.is_match(value) {
+            return Err(invalid(
+                field_name,
+                codes::PATTERN,
+                &[("pattern", pattern.to_string())],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate a semantic version that satisfies `requirement`, e.g. `">=1.2, <2"`
+    #[cfg(feature = "validation")]
+    pub fn semver(value: &str, requirement: &str, field_name: &str) -> ConfigResult<()> {
+        let requirement = semver::VersionReq::parse(requirement).map_err(|e| {
+            ConfigError::Validation(format!("Invalid version requirement '{requirement}': {e}"))
+        })?;
+        let version = semver::Version::parse(value).map_err(|e| {
+            invalid(
+                field_name,
+                codes::SEMVER,
+                &[("kind", "parse".to_string()), ("reason", e.to_string())],
+            )
+        })?;
+        if !requirement.matches(&version) {
+            return Err(invalid(
+                field_name,
+                codes::SEMVER,
+                &[
+                    ("kind", "requirement".to_string()),
+                    ("version", version.to_string()),
+                    ("requirement", requirement.to_string()),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate a semantic version requirement, e.g. `"^1.4"`
+    #[cfg(feature = "validation")]
+    pub fn semver_requirement(value: &str, field_name: &str) -> ConfigResult<()> {
+        semver::VersionReq::parse(value).map(drop).map_err(|e| {
+            invalid(
+                field_name,
+                codes::SEMVER_REQUIREMENT,
+                &[("reason", e.to_string())],
+            )
+        })
+    }
+
+    /// Validate that a file exists and allows `access`
+    pub fn file(path: impl AsRef<Path>, access: Access, field_name: &str) -> ConfigResult<()> {
+        let path = path.as_ref();
+        let metadata = path_metadata(path, field_name)?;
+        if !metadata.is_file() {
+            return Err(invalid(
+                field_name,
+                codes::NOT_A_FILE,
+                &[("path", path.display().to_string())],
+            ));
+        }
+        if access.read && std::fs::File::open(path).is_err() {
+            return Err(permission_denied(path, "read", field_name));
+        }
+        let writable = || std::fs::OpenOptions::new().write(true).open(path).is_ok();
+        if access.write && !writable() {
+            return Err(permission_denied(path, "write", field_name));
+        }
+        check_execute(path, &metadata, access, field_name)
+    }
+
+    /// Validate that a directory exists and allows `access`
+    pub fn directory(path: impl AsRef<Path>, access: Access, field_name: &str) -> ConfigResult<()> {
+        let path = path.as_ref();
+        let metadata = path_metadata(path, field_name)?;
+        if !metadata.is_dir() {
+            return Err(invalid(
+                field_name,
+                codes::NOT_A_DIRECTORY,
+                &[("path", path.display().to_string())],
+            ));
+        }
+        if access.read && std::fs::read_dir(path).is_err() {
+            return Err(permission_denied(path, "read", field_name));
+        }
+        if access.write && !is_writable_dir(path) {
+            return Err(permission_denied(path, "write", field_name));
+        }
+        check_execute(path, &metadata, access, field_name)
+    }
+
+    /// Validate that a duration is within `min..=max`
+    pub fn duration(
+        value: impl Into<Duration>,
+        min: impl Into<Duration>,
+        max: impl Into<Duration>,
+        field_name: &str,
+    ) -> ConfigResult<()> {
+        let (value, min, max) = (value.into(), min.into(), max.into());
+        if value < min || value > max {
+            return Err(invalid(
+                field_name,
+                codes::DURATION,
+                &[
+                    ("min", ConfigDuration::from(min).to_string()),
+                    ("max", ConfigDuration::from(max).to_string()),
+                    ("actual", ConfigDuration::from(value).to_string()),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate that a size is within `min..=max`
+    pub fn byte_size(
+        value: ByteSize,
+        min: ByteSize,
+        max: ByteSize,
+        field_name: &str,
+    ) -> ConfigResult<()> {
+        if value < min || value > max {
+            return Err(invalid(
+                field_name,
+                codes::BYTE_SIZE,
+                &[
+                    ("min", min.to_string()),
+                    ("max", max.to_string()),
+                    ("actual", value.to_string()),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate that a percentage is within `min..=max`, e.g. `0.0..=100.0`
+    pub fn percent(value: Percent, min: f64, max: f64, field_name: &str) -> ConfigResult<()> {
+        if value.value() < min || value.value() > max {
+            return Err(invalid(
+                field_name,
+                codes::PERCENT,
+                &[
+                    ("min", min.to_string()),
+                    ("max", max.to_string()),
+                    ("actual", value.to_string()),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate that a value is one of `allowed`
+    pub fn one_of<T>(value: &T, allowed: &[T], field_name: &str) -> ConfigResult<()>
+    where
+        T: PartialEq + std::fmt::Debug,
+    {
+        if !allowed.contains(value) {
+            return Err(invalid(
+                field_name,
+                codes::ONE_OF,
+                &[
+                    ("allowed", format!("{allowed:?}")),
+                    ("actual", format!("{value:?}")),
+                ],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate that a list holds no duplicates
+    pub fn unique<T>(items: &[T], field_name: &str) -> ConfigResult<()>
+    where
+        T: Eq + std::hash::Hash + std::fmt::Debug,
+    {
+        let mut seen = std::collections::HashSet::new();
+        let duplicates: Vec<_> = items.iter().filter(|item| !seen.insert(*item)).collect();
+        if !duplicates.is_empty() {
+            return Err(invalid(
+                field_name,
+                codes::UNIQUE,
+                &[("duplicates", format!("{duplicates:?}"))],
+            ));
         }
         Ok(())
     }
 }
 
+/// Check a hostname against RFC 1123, a trailing dot is allowed
+fn is_hostname(value: &str) -> bool {
+    let name = value.strip_suffix('.').unwrap_or(value);
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// Parse an RFC 3986 URI, returning its scheme or why it is invalid
+fn parse_url(value: &str) -> Result<&str, &'static str> {
+    let (scheme, rest) = value.split_once(':').ok_or("missing scheme")?;
+    let mut scheme_chars = scheme.chars();
+    if !scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        || !scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    {
+        return Err("invalid scheme");
+    }
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c);
+    if !rest.chars().all(allowed) {
+        return Err("contains characters that must be percent-encoded");
+    }
+    let bytes = rest.as_bytes();
+    for (i, _) in rest.match_indices('%') {
+        let hex = bytes.get(i + 1..i + 3);
+        if !hex.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+            return Err("invalid percent-encoding");
+        }
+    }
+
+    let Some(after_slashes) = rest.strip_prefix("//") else {
+        return if rest.is_empty() {
+            Err("missing path")
+        } else {
+            Ok(scheme)
+        };
+    };
+    let authority = after_slashes
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let (host, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+        let (ip, port) = bracketed.split_once(']').ok_or("unclosed IPv6 address")?;
+        ip.parse::<std::net::Ipv6Addr>()
+            .map_err(|_| "invalid IPv6 address")?;
+        (None, port)
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => (Some(host), port),
+            None => (Some(host_port), ""),
+        }
+    };
+    let port = port.strip_prefix(':').unwrap_or(port);
+    if !port.is_empty()
+        && (!port.bytes().all(|b| b.is_ascii_digit()) || port.parse::<u16>().is_err())
+    {
+        return Err("invalid port");
+    }
+    match host {
+        Some("") if scheme.eq_ignore_ascii_case("file") => Ok(scheme),
+        Some("") => Err("missing host"),
+        Some(host) if host.parse::<std::net::Ipv4Addr>().is_ok() || is_hostname(host) => Ok(scheme),
+        Some(_) => Err("invalid host"),
+        None => Ok(scheme),
+    }
+}
+
+fn path_metadata(path: &Path, field_name: &str) -> ConfigResult<std::fs::Metadata> {
+    std::fs::metadata(path).map_err(|e| {
+        invalid(
+            field_name,
+            codes::PATH_NOT_FOUND,
+            &[
+                ("path", path.display().to_string()),
+                ("reason", e.to_string()),
+            ],
+        )
+    })
+}
+
+/// `kind` is the missing access: `read`, `write` or `execute`
+fn permission_denied(path: &Path, kind: &str, field_name: &str) -> ConfigError {
+    invalid(
+        field_name,
+        codes::PERMISSION_DENIED,
+        &[
+            ("kind", kind.to_string()),
+            ("path", path.display().to_string()),
+        ],
+    )
+}
+
+/// Check whether a file can be created in `dir`, leaving nothing behind
+fn is_writable_dir(dir: &Path) -> bool {
+    let probe = dir.join(format!(".rusty-config-{}", uuid::Uuid::new_v4()));
+    let created = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .is_ok();
+    if created {
+        let _ = std::fs::remove_file(&probe);
+    }
+    created
+}
+
+/// Check execute permission from the mode bits
+#[allow(unused_variables)]
+fn check_execute(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+    access: Access,
+    field_name: &str,
+) -> ConfigResult<()> {
+    #[cfg(unix)]
+    if access.execute {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(permission_denied(path, "execute", field_name));
+        }
+    }
+    Ok(())
+}
+
 /// Predefined validation rules for common use cases
 pub struct CommonValidators;
 
@@ -208,12 +1051,7 @@ impl CommonValidators {
     /// Validate a logging config
     pub fn validate_logging_config(level: &str) -> ConfigResult<()> {
         let valid_levels = ["trace", "debug", "info", "warn", "error"];
-        if !valid_levels.contains(&level.to_lowercase().as_str()) {
-            return Err(ConfigError::Validation(format!(
-                "Logging level '{level}' is invalid. Valid values: {valid_levels:?}"
-            )));
-        }
-        Ok(())
+        TypeValidator::one_of(&level.to_lowercase().as_str(), &valid_levels, "level")
     }
 }
 
@@ -265,6 +1103,140 @@ mod tests {
         assert!(config.validate().await.is_err());
     }
 
+    #[derive(Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[async_trait]
+    impl Validatable for Server {
+        async fn validate(&self) -> ConfigResult<()> {
+            validate(self).await
+        }
+
+        async fn validate_into(&self, ctx: &mut ValidationContext) {
+            ctx.check("host", TypeValidator::not_empty(&self.host, "host"))
+                .check("port", TypeValidator::port(self.port, "port"));
+        }
+    }
+
+    #[derive(Debug)]
+    struct Cluster {
+        name: String,
+        servers: Vec<Server>,
+        primary: Server,
+    }
+
+    #[async_trait]
+    impl Validatable for Cluster {
+        async fn validate(&self) -> ConfigResult<()> {
+            validate(self).await
+        }
+
+        async fn validate_into(&self, ctx: &mut ValidationContext) {
+            ctx.check("name", TypeValidator::not_empty(&self.name, "name"));
+            ctx.nested("primary", &self.primary).await;
+            ctx.nested_each("servers", &self.servers).await;
+        }
+    }
+
+    fn server(host: &str, port: u16) -> Server {
+        Server {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_collects_all_errors_with_paths() {
+        let cluster = Cluster {
+            name: String::new(),
+            primary: server("", 80),
+            servers: vec![server("a", 80), server("b", 443), server("", 0)],
+        };
+
+        let Err(ConfigError::ValidationErrors(errors)) = validate(&cluster).await else {
+            panic!("expected all validation errors");
+        };
+        let fields: Vec<_> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["name", "primary.host", "servers[2].host", "servers[2].port"]
+        );
+        assert!(errors[3].message.contains("valid port"));
+
+        // The default `validate` reports the same list
+        assert!(matches!(
+            cluster.validate().await,
+            Err(ConfigError::ValidationErrors(errors)) if errors.len() == 4
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_forwarding_without_validate_into_fails_instead_of_recursing() {
+        struct Forwarding {
+            inner: Server,
+        }
+
+        #[async_trait]
+        impl Validatable for Forwarding {
+            async fn validate(&self) -> ConfigResult<()> {
+                // Nested values at the same address still validate
+                self.inner.validate().await?;
+                validate(self).await
+            }
+        }
+
+        let forwarding = Forwarding {
+            inner: server("a", 80),
+        };
+        let Err(ConfigError::ValidationErrors(errors)) = validate(&forwarding).await else {
+            panic!("expected the forwarding error");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].message.contains("validate_into"),
+            "{}",
+            errors[0].message
+        );
+
+        let invalid = Forwarding {
+            inner: server("", 80),
+        };
+        assert!(matches!(
+            invalid.validate().await,
+            Err(ConfigError::ValidationErrors(errors)) if errors[0].field == "host"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_context_wraps_first_error_validators() {
+        let mut ctx = ValidationContext::new();
+        ctx.each(
+            "configs",
+            &[TestConfig {
+                name: String::new(),
+                port: 8080,
+                email: "test@example.com".to_string(),
+                url: "https://example.com".to_string(),
+            }],
+            |ctx, config| {
+                ctx.check("name", TypeValidator::not_empty(&config.name, "name"));
+            },
+        );
+        ctx.field("limits", |ctx| {
+            ctx.error("max", "must not exceed 10")
+                .warning("min", "is unusually low");
+        });
+
+        let result = ctx.into_result();
+        assert!(!result.is_valid);
+        assert_eq!(result.errors[0].field, "configs[0].name");
+        assert_eq!(result.errors[1].field, "limits.max");
+        assert_eq!(result.warnings[0].field, "limits.min");
+    }
+
     #[test]
     fn test_type_validators() {
         assert!(TypeValidator::not_empty("test", "field").is_ok());
@@ -285,4 +1257,227 @@ mod tests {
         assert!(TypeValidator::port(8080, "field").is_ok());
         assert!(TypeValidator::port(0, "field").is_err());
     }
+
+    fn code(result: ConfigResult<()>) -> String {
+        match result {
+            Err(ConfigError::ValidationErrors(errors)) => errors[0].code.clone(),
+            other => panic!("expected a coded validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_network_validators() {
+        for url in [
+            "https://example.com/path?q=1#top",
+            "http://user:pw@127.0.0.1:8080",
+            "https://[::1]:443/",
+        ] {
+            assert!(TypeValidator::url(url, "url").is_ok(), "{url}");
+        }
+        assert_eq!(
+            code(TypeValidator::url("https://exa mple.com", "url")),
+            codes::URL
+        );
+        assert_eq!(code(TypeValidator::url("https://", "url")), codes::URL);
+        assert_eq!(
+            code(TypeValidator::url("http://host:99999", "url")),
+            codes::URL
+        );
+        assert_eq!(
+            code(TypeValidator::url("ftp://example.com", "url")),
+            codes::URL_SCHEME
+        );
+        assert!(TypeValidator::url_with_schemes("file:///etc/hosts", &["file"], "url").is_ok());
+        assert!(
+            TypeValidator::url_with_schemes("postgres://db:5432/app", &["postgres"], "url").is_ok()
+        );
+
+        assert!(TypeValidator::email("first.last+tag@mail.example.com", "email").is_ok());
+        assert_eq!(
+            code(TypeValidator::email("a@b@c.com", "email")),
+            codes::EMAIL
+        );
+        assert_eq!(
+            code(TypeValidator::email("a..b@c.com", "email")),
+            codes::EMAIL
+        );
+
+        assert!(TypeValidator::hostname("db-1.internal.", "host").is_ok());
+        assert_eq!(
+            code(TypeValidator::hostname("-db.internal", "host")),
+            codes::HOSTNAME
+        );
+        assert_eq!(
+            code(TypeValidator::hostname(&"a".repeat(64), "host")),
+            codes::HOSTNAME
+        );
+
+        assert!(TypeValidator::ip("::1", "ip").is_ok());
+        assert_eq!(code(TypeValidator::ipv4("::1", "ip")), codes::IPV4);
+        assert_eq!(code(TypeValidator::ipv6("10.0.0.1", "ip")), codes::IPV6);
+        assert!(TypeValidator::cidr("10.0.0.0/8", "net").is_ok());
+        assert!(TypeValidator::cidr("fd00::/128", "net").is_ok());
+        assert_eq!(code(TypeValidator::cidr("10.0.0.0/33", "net")), codes::CIDR);
+        assert_eq!(code(TypeValidator::cidr("10.0.0.0", "net")), codes::CIDR);
+        assert!(TypeValidator::socket_addr("[::1]:8080", "listen").is_ok());
+        assert_eq!(
+            code(TypeValidator::socket_addr("localhost", "listen")),
+            codes::SOCKET_ADDR
+        );
+    }
+
+    #[test]
+    fn test_path_and_collection_validators() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("cert.pem");
+        std::fs::write(&file, "cert").unwrap();
+
+        assert!(TypeValidator::file(&file, Access::READ_WRITE, "cert").is_ok());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "cert");
+        assert!(TypeValidator::directory(temp_dir.path(), Access::READ_WRITE, "dir").is_ok());
+        // The write probe is removed again
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+        assert_eq!(
+            code(TypeValidator::directory(&file, Access::ANY, "dir")),
+            codes::NOT_A_DIRECTORY
+        );
+        assert_eq!(
+            code(TypeValidator::file(temp_dir.path(), Access::ANY, "cert")),
+            codes::NOT_A_FILE
+        );
+        assert_eq!(
+            code(TypeValidator::file(
+                temp_dir.path().join("missing"),
+                Access::ANY,
+                "cert"
+            )),
+            codes::PATH_NOT_FOUND
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            code(TypeValidator::file(&file, Access::EXECUTE, "cert")),
+            codes::PERMISSION_DENIED
+        );
+
+        assert!(TypeValidator::one_of(&"json", &["json", "yaml"], "format").is_ok());
+        assert_eq!(
+            code(TypeValidator::one_of(&"xml", &["json", "yaml"], "format")),
+            codes::ONE_OF
+        );
+        assert!(TypeValidator::unique(&[1, 2, 3], "ports").is_ok());
+        assert_eq!(
+            code(TypeValidator::unique(&[80, 443, 80], "ports")),
+            codes::UNIQUE
+        );
+    }
+
+    #[test]
+    fn test_unit_validators() {
+        let timeout: ConfigDuration = "90s".parse().unwrap();
+        assert!(TypeValidator::duration(
+            timeout,
+            Duration::from_secs(1),
+            Duration::from_secs(300),
+            "timeout"
+        )
+        .is_ok());
+        let err = TypeValidator::duration(
+            timeout,
+            Duration::ZERO,
+            ConfigDuration::from_secs(60),
+            "timeout",
+        );
+        assert!(err
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("between 0s and 1m (currently: 1m30s)"));
+        assert_eq!(code(err), codes::DURATION);
+
+        assert!(TypeValidator::byte_size(
+            ByteSize::mib(10),
+            ByteSize::b(0),
+            ByteSize::mib(10),
+            "max_body"
+        )
+        .is_ok());
+        assert_eq!(
+            code(TypeValidator::byte_size(
+                ByteSize::gib(1),
+                ByteSize::b(0),
+                ByteSize::mib(10),
+                "max_body"
+            )),
+            codes::BYTE_SIZE
+        );
+        assert_eq!(
+            code(TypeValidator::percent(
+                Percent::new(120.0),
+                0.0,
+                100.0,
+                "ratio"
+            )),
+            codes::PERCENT
+        );
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_pattern_and_semver_validators() {
+        let pattern = regex::Regex::new(r"^[a-z][a-z0-9_]*$").unwrap();
+        assert!(TypeValidator::pattern("app_db", &pattern, "name").is_ok());
+        assert_eq!(
+            code(TypeValidator::pattern("App", &pattern, "name")),
+            codes::PATTERN
+        );
+
+        assert!(TypeValidator::semver("1.4.2", ">=1.2, <2", "version").is_ok());
+        assert_eq!(
+            code(TypeValidator::semver("2.0.0", ">=1.2, <2", "version")),
+            codes::SEMVER
+        );
+        assert_eq!(
+            code(TypeValidator::semver("1.4", "*", "version")),
+            codes::SEMVER
+        );
+        assert!(TypeValidator::semver_requirement("^1.4", "requires").is_ok());
+        assert_eq!(
+            code(TypeValidator::semver_requirement("latest", "requires")),
+            codes::SEMVER_REQUIREMENT
+        );
+    }
+
+    #[test]
+    fn test_localized_messages() {
+        let Err(ConfigError::ValidationErrors(errors)) = TypeValidator::range(0, 1, 64, "workers")
+        else {
+            panic!("expected validation errors");
+        };
+        let error = &errors[0];
+        assert_eq!(error.message, "must be between 1 and 64 (currently: 0)");
+        assert_eq!(error.localized(&i18n::EN), error.message);
+        assert_eq!(
+            error.localized(&i18n::DE),
+            "muss zwischen 1 und 64 liegen (aktuell: 0)"
+        );
+
+        let ConfigError::ValidationErrors(errors) =
+            permission_denied(Path::new("/etc/app"), "write", "data_dir")
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            errors[0].localized(&i18n::DE),
+            "'/etc/app' ist nicht beschreibbar"
+        );
+
+        // Errors without parameters keep their message in every locale
+        let custom = ValidationError::new("name", "is reserved").with_code(codes::ONE_OF);
+        assert_eq!(custom.localized(&i18n::DE), "is reserved");
+
+        let json = serde_json::to_value(error).unwrap();
+        assert_eq!(json["params"]["max"], "64");
+        let parsed: ValidationError = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.localized(&i18n::DE), error.localized(&i18n::DE));
+    }
 }
@@ -0,0 +1,888 @@
+use crate::validator::{codes, ValidationError};
+use crate::{ConfigError, ConfigResult};
+use serde::de::{
+    self, value::MapAccessDeserializer, value::MapDeserializer, value::SeqDeserializer,
+    DeserializeOwned, IntoDeserializer, Visitor,
+};
+use serde_json::{Map, Number};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Format-independent value tree used by loaders, overlays and merges
+pub type Value = serde_json::Value;
+
+/// Insert a value at a dotted key path, creating intermediate tables
+///
+/// Numeric segments index into existing lists (`servers.0.port`); an index
+/// one past the end appends.
+pub fn insert_path(root: &mut Value, path: &str, value: Value) {
+    let mut current = root;
+    let mut segments = path
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .peekable();
+
+    while let Some(segment) = segments.next() {
+        let is_last = segments.peek().is_none();
+
+        let array_index = match &*current {
+            Value::Array(items) => segment.parse::<usize>().ok().filter(|i| *i <= items.len()),
+            _ => None,
+        };
+        if let Some(index) = array_index {
+            let items = current
+                .as_array_mut()
+                .expect("value was checked to be a list");
+            if index == items.len() {
+                items.push(Value::Null);
+            }
+            if is_last {
+                items[index] = value;
+                return;
+            }
+            current = &mut items[index];
+            continue;
+        }
+
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let table = current
+            .as_object_mut()
+            .expect("value was just made an object");
+
+        if is_last {
+            table.insert(segment.to_string(), value);
+            return;
+        }
+
+        current = table
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    // Empty path: replace the root itself
+    *current = value;
+}
+
+/// Join a dotted key path and one more segment
+pub(crate) fn child_path(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{path}.{segment}")
+    }
+}
+
+/// Look up a value by dotted key path
+pub fn get_path<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(root, |current, segment| match current {
+            Value::Object(table) => table.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+/// Look up a value by dotted key path for modification
+pub fn get_path_mut<'a>(root: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(root, |current, segment| match current {
+            Value::Object(table) => table.get_mut(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+            _ => None,
+        })
+}
+
+/// Remove the value at a dotted key path, keeping the order of the rest
+pub fn remove_path(root: &mut Value, path: &str) -> Option<Value> {
+    let (parent_path, last) = path.rsplit_once('.').unwrap_or(("", path));
+    let mut parent = root;
+    for segment in parent_path.split('.').filter(|segment| !segment.is_empty()) {
+        parent = match parent {
+            Value::Object(table) => table.get_mut(segment)?,
+            Value::Array(items) => items.get_mut(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match parent {
+        Value::Object(table) => table.shift_remove(last),
+        Value::Array(items) => {
+            let index = last.parse::<usize>().ok().filter(|i| *i < items.len())?;
+            Some(items.remove(index))
+        }
+        _ => None,
+    }
+}
+
+/// Deep-merge `overlay` into `base`
+///
+/// Tables are merged key by key, any other value in `overlay` replaces the
+/// one in `base` (lists are replaced, not concatenated).
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base_table), Value::Object(overlay_table)) => {
+            for (key, overlay_child) in overlay_table {
+                match base_table.get_mut(&key) {
+                    Some(base_child) => deep_merge(base_child, overlay_child),
+                    None => {
+                        base_table.insert(key, overlay_child);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Flatten a value tree into dotted key paths and their leaf values
+///
+/// Arrays are flattened with their index as key segment (`servers.0.port`).
+pub fn flatten(value: &Value) -> Vec<(String, Value)> {
+    let mut entries = Vec::new();
+    flatten_into(value, String::new(), &mut entries);
+    entries
+}
+
+fn flatten_into(value: &Value, prefix: String, entries: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(table) if !table.is_empty() => {
+            for (key, child) in table {
+                flatten_into(child, child_path(&prefix, key), entries);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                flatten_into(child, child_path(&prefix, &index.to_string()), entries);
+            }
+        }
+        _ => entries.push((prefix, value.clone())),
+    }
+}
+
+/// A difference between two value trees, see [`diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl Change {
+    /// Dotted key path of the changed value, empty for the root
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {path} = {value}"),
+            Change::Removed { path, value } => write!(f, "- {path} = {value}"),
+            Change::Changed { path, old, new } => write!(f, "~ {path}: {old} -> {new}"),
+        }
+    }
+}
+
+/// Compare two value trees key by key, independent of their source format
+///
+/// Tables are compared per key and lists per index, with paths like
+/// [`flatten`]. Key order is ignored.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(old, new, String::new(), &mut changes);
+    changes
+}
+
+fn diff_into(old: &Value, new: &Value, path: String, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old_table), Value::Object(new_table)) => {
+            for (key, old_child) in old_table {
+                match new_table.get(key) {
+                    Some(new_child) => {
+                        diff_into(old_child, new_child, child_path(&path, key), changes)
+                    }
+                    None => changes.push(Change::Removed {
+                        path: child_path(&path, key),
+                        value: old_child.clone(),
+                    }),
+                }
+            }
+            for (key, new_child) in new_table {
+                if !old_table.contains_key(key) {
+                    changes.push(Change::Added {
+                        path: child_path(&path, key),
+                        value: new_child.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (index, (old_child, new_child)) in old_items.iter().zip(new_items).enumerate() {
+                diff_into(
+                    old_child,
+                    new_child,
+                    child_path(&path, &index.to_string()),
+                    changes,
+                );
+            }
+            for (index, value) in old_items.iter().enumerate().skip(new_items.len()) {
+                changes.push(Change::Removed {
+                    path: child_path(&path, &index.to_string()),
+                    value: value.clone(),
+                });
+            }
+            for (index, value) in new_items.iter().enumerate().skip(old_items.len()) {
+                changes.push(Change::Added {
+                    path: child_path(&path, &index.to_string()),
+                    value: value.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => changes.push(Change::Changed {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Turn tables whose keys are exactly `0..n` into arrays, recursively
+///
+/// Flat formats can only express lists as indexed keys; this restores them.
+pub fn normalize_arrays(value: &mut Value) {
+    match value {
+        Value::Object(table) => {
+            for child in table.values_mut() {
+                normalize_arrays(child);
+            }
+
+            let is_sequence = !table.is_empty()
+                && (0..table.len()).all(|index| table.contains_key(&index.to_string()));
+            if is_sequence {
+                let items = (0..table.len())
+                    .map(|index| table.remove(&index.to_string()).unwrap_or(Value::Null))
+                    .collect();
+                *value = Value::Array(items);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_arrays),
+        _ => {}
+    }
+}
+
+/// Render a scalar leaf as plain text for flat formats
+pub fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Deserialize a value tree into `T`, coercing string leaves where needed
+///
+/// Flat formats (INI, properties, dotenv, ...) carry every value as a string.
+/// This accepts `"8080"` for a `u16` field or `"true"` for a `bool` field,
+/// while string fields keep their text unchanged.
+pub fn from_value_lenient<T>(value: Value, format: &str) -> ConfigResult<T>
+where
+    T: DeserializeOwned,
+{
+    deserialize_tree(value, format, Coercion::All, false).map(|(data, _)| data)
+}
+
+/// Like [`from_value_lenient`], also reporting keys no struct field consumed
+///
+/// Each unknown key is reported with its path (`servers[2].pool_sise`) and
+/// the closest field name as a suggestion, with the code `unknown_key`.
+/// Keys read into maps are never unknown.
+///
+/// serde reads a struct with a `#[serde(flatten)]` field as a map and
+/// hands every key it does not know to the flattened field, so stray keys
+/// in such a struct are not reported. Its other fields are still checked.
+pub fn from_value_checked<T>(value: Value, format: &str) -> ConfigResult<(T, Vec<ValidationError>)>
+where
+    T: DeserializeOwned,
+{
+    deserialize_tree(value, format, Coercion::All, true)
+}
+
+/// Deserialize a value tree, coercing only the leaves at or below `paths`
+///
+/// Used for documents in typed formats, where only `--set` overrides and
+/// resolved secrets arrive as text. Unknown keys are reported like
+/// [`from_value_checked`] does when `check_unknown` is set.
+pub(crate) fn from_value_coercing<T>(
+    value: Value,
+    format: &str,
+    paths: Vec<String>,
+    check_unknown: bool,
+) -> ConfigResult<(T, Vec<ValidationError>)>
+where
+    T: DeserializeOwned,
+{
+    let coercion = Coercion::Paths {
+        path: String::new(),
+        paths: paths.into(),
+    };
+    deserialize_tree(value, format, coercion, check_unknown)
+}
+
+fn deserialize_tree<T>(
+    value: Value,
+    format: &str,
+    coercion: Coercion,
+    check_unknown: bool,
+) -> ConfigResult<(T, Vec<ValidationError>)>
+where
+    T: DeserializeOwned,
+{
+    let unknown = Rc::new(RefCell::new(Vec::new()));
+    let tracker = KeyTracker {
+        path: String::new(),
+        unknown: check_unknown.then(|| Rc::clone(&unknown)),
+        coercion,
+    };
+    let data = T::deserialize(Lenient(value, tracker))
+        .map_err(|err| ConfigError::Serde(format!("{format}: {err}")))?;
+    Ok((data, unknown.take()))
+}
+
+/// Edit distance between two strings, counting swapped neighbours as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// The candidate closest to `key`, if it is close enough to be a typo
+fn closest_match<'a>(key: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Which string leaves a [`Lenient`] deserializer reads as other types
+#[derive(Clone)]
+enum Coercion {
+    /// Every leaf, for trees read from flat formats
+    All,
+    /// Leaves at or below one of `paths`; `path` is the current dotted path
+    Paths { path: String, paths: Rc<[String]> },
+}
+
+impl Coercion {
+    fn child(&self, segment: &str) -> Self {
+        match self {
+            Coercion::All => Coercion::All,
+            Coercion::Paths { path, paths } => Coercion::Paths {
+                path: child_path(path, segment),
+                paths: Rc::clone(paths),
+            },
+        }
+    }
+
+    fn applies(&self) -> bool {
+        match self {
+            Coercion::All => true,
+            Coercion::Paths { path, paths } => paths.iter().any(|coerced| {
+                path.strip_prefix(coerced.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            }),
+        }
+    }
+}
+
+/// Path of the value being deserialized, and where unknown keys are collected
+#[derive(Clone)]
+struct KeyTracker {
+    path: String,
+    unknown: Option<Rc<RefCell<Vec<ValidationError>>>>,
+    coercion: Coercion,
+}
+
+impl KeyTracker {
+    fn key(&self, key: &str) -> Self {
+        let path = match &self.unknown {
+            Some(_) => child_path(&self.path, key),
+            None => String::new(),
+        };
+        self.with_path(path, key)
+    }
+
+    fn index(&self, index: usize) -> Self {
+        let path = match &self.unknown {
+            Some(_) => format!("{}[{index}]", self.path),
+            None => String::new(),
+        };
+        self.with_path(path, &index.to_string())
+    }
+
+    fn with_path(&self, path: String, segment: &str) -> Self {
+        Self {
+            path,
+            unknown: self.unknown.clone(),
+            coercion: self.coercion.child(segment),
+        }
+    }
+
+    /// Record the keys of `table` that are not in `fields`
+    fn check_fields(&self, table: &Map<String, Value>, fields: &[&str]) {
+        let Some(unknown) = &self.unknown else {
+            return;
+        };
+        for key in table.keys().filter(|key| !fields.contains(&key.as_str())) {
+            let mut params = vec![("key", key.clone())];
+            if let Some(suggestion) = closest_match(key, fields) {
+                params.extend([
+                    ("kind", "suggestion".to_string()),
+                    ("suggestion", suggestion.to_string()),
+                ]);
+            }
+            unknown.borrow_mut().push(ValidationError::templated(
+                self.key(key).path,
+                codes::UNKNOWN_KEY,
+                &params,
+            ));
+        }
+    }
+
+    fn entries(&self, table: Map<String, Value>) -> impl Iterator<Item = (String, Lenient)> + '_ {
+        table.into_iter().map(move |(key, value)| {
+            let tracker = self.key(&key);
+            (key, Lenient(value, tracker))
+        })
+    }
+}
+
+/// Deserializer over a [`Value`] that parses strings into the requested scalar type
+///
+/// The reverse holds for string fields, which read numbers and booleans as text.
+struct Lenient(Value, KeyTracker);
+
+type LenientError = de::value::Error;
+
+impl<'de> IntoDeserializer<'de, LenientError> for Lenient {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Parse a boolean the way flat formats write them (`true`, `yes`, `on`, ...)
+pub(crate) fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a number from text, preferring integers
+pub(crate) fn parse_number(text: &str) -> Option<Number> {
+    let text = text.trim();
+    text.parse::<u64>()
+        .map(Number::from)
+        .or_else(|_| text.parse::<i64>().map(Number::from))
+        .ok()
+        .or_else(|| text.parse::<f64>().ok().and_then(Number::from_f64))
+}
+
+impl Lenient {
+    fn visit_number<'de, V>(number: &Number, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(n) = number.as_u64() {
+            visitor.visit_u64(n)
+        } else if let Some(n) = number.as_i64() {
+            visitor.visit_i64(n)
+        } else {
+            visitor.visit_f64(number.as_f64().unwrap_or_default())
+        }
+    }
+}
+
+macro_rules! lenient_number {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, LenientError>
+            where
+                V: Visitor<'de>,
+            {
+                match &self.0 {
+                    Value::String(text) if self.1.coercion.applies() => match parse_number(text) {
+                        Some(number) => Self::visit_number(&number, visitor),
+                        None => self.deserialize_any(visitor),
+                    },
+                    _ => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Lenient {
+    type Error = LenientError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(number) => Self::visit_number(&number, visitor),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(items) => {
+                let tracker = self.1;
+                let mut seq = SeqDeserializer::new(
+                    items
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, item)| Lenient(item, tracker.index(index))),
+                );
+                let result = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(result)
+            }
+            Value::Object(table) => {
+                let mut map = MapDeserializer::new(self.1.entries(table));
+                let result = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(result)
+            }
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        match &self.0 {
+            Value::String(text) if self.1.coercion.applies() => match parse_bool(text) {
+                Some(b) => visitor.visit_bool(b),
+                None => self.deserialize_any(visitor),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.1.coercion.applies() {
+            return self.deserialize_any(visitor);
+        }
+        match self.0 {
+            Value::Number(number) => visitor.visit_string(number.to_string()),
+            Value::Bool(b) => visitor.visit_string(b.to_string()),
+            other => Lenient(other, self.1).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    lenient_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(Lenient(other, self.1)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Object(table) => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::new(self.1.entries(table)),
+            )),
+            other => Lenient(other, self.1).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, LenientError>
+    where
+        V: Visitor<'de>,
+    {
+        if let Value::Object(table) = &self.0 {
+            self.1.check_fields(table, fields);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        secure: bool,
+        ratio: Option<f64>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_unknown_keys_with_suggestions() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Pool {
+            pool_size: u32,
+            #[serde(default)]
+            labels: std::collections::HashMap<String, String>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Cluster {
+            servers: Vec<Pool>,
+            primary: Option<Pool>,
+        }
+
+        let (_, unknown) = from_value_checked::<Cluster>(
+            json!({
+                "servers": [{"pool_size": 1}, {"pool_size": 2, "pool_sise": 20}],
+                "primary": {"pool_size": 3, "labels": {"any": "key"}},
+                "replicas": 2,
+            }),
+            "test",
+        )
+        .unwrap();
+        let unknown: Vec<_> = unknown
+            .iter()
+            .map(|error| (error.field.as_str(), error.message.as_str()))
+            .collect();
+        assert_eq!(
+            unknown,
+            [
+                ("replicas", "unknown key 'replicas'"),
+                (
+                    "servers[1].pool_sise",
+                    "unknown key 'pool_sise', did you mean 'pool_size'?"
+                ),
+            ]
+        );
+        assert_eq!(closest_match("prot", &["host", "port"]), Some("port"));
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("wrokers", "workers"), 1);
+    }
+
+    #[test]
+    fn test_unknown_keys_next_to_flattened_fields_are_not_checked() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Pool {
+            pool_size: u32,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Database {
+            url: String,
+            #[serde(flatten)]
+            pool: Pool,
+            replica: Option<Pool>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct App {
+            database: Database,
+        }
+
+        let (_, unknown) = from_value_checked::<App>(
+            json!({
+                "database": {
+                    "url": "postgres://db",
+                    "pool_size": 5,
+                    "pool_sise": 10,
+                    "replica": {"pool_size": 1, "pool_sise": 2},
+                },
+                "verbose": true,
+            }),
+            "test",
+        )
+        .unwrap();
+        let fields: Vec<_> = unknown.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["verbose", "database.replica.pool_sise"]);
+    }
+
+    #[test]
+    fn test_insert_and_get_path() {
+        let mut root = Value::Object(Map::new());
+        insert_path(&mut root, "server.host", json!("localhost"));
+        insert_path(&mut root, "server.port", json!(8080));
+
+        assert_eq!(root, json!({"server": {"host": "localhost", "port": 8080}}));
+        assert_eq!(get_path(&root, "server.port"), Some(&json!(8080)));
+        assert_eq!(get_path(&root, "server.missing"), None);
+
+        insert_path(&mut root, "server.tags", json!(["a"]));
+        insert_path(&mut root, "server.tags.0", json!("b"));
+        insert_path(&mut root, "server.tags.1", json!("c"));
+        assert_eq!(get_path(&root, "server.tags"), Some(&json!(["b", "c"])));
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let mut base = json!({"server": {"host": "localhost", "port": 8080}, "tags": ["a"]});
+        deep_merge(
+            &mut base,
+            json!({"server": {"port": 9090}, "tags": ["b", "c"], "debug": true}),
+        );
+
+        assert_eq!(
+            base,
+            json!({
+                "server": {"host": "localhost", "port": 9090},
+                "tags": ["b", "c"],
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = json!({"server": {"host": "a", "port": 80}, "tags": ["x", "y"], "debug": true});
+        let new = json!({"tags": ["x"], "server": {"port": 8080, "host": "a"}, "workers": 4});
+        let changes: Vec<_> = diff(&old, &new).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "~ server.port: 80 -> 8080",
+                "- tags.1 = \"y\"",
+                "- debug = true",
+                "+ workers = 4",
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_flatten_and_normalize_roundtrip() {
+        let original = json!({"servers": [{"port": 1}, {"port": 2}], "name": "x"});
+
+        let mut rebuilt = Value::Object(Map::new());
+        for (key, value) in flatten(&original) {
+            insert_path(&mut rebuilt, &key, value);
+        }
+        normalize_arrays(&mut rebuilt);
+
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn test_from_value_lenient() {
+        let value = json!({
+            "host": "10.0.0.1",
+            "port": "8080",
+            "secure": "true",
+            "ratio": "0.5",
+            "tags": ["1", "two"]
+        });
+
+        let server: Server = from_value_lenient(value, "test").unwrap();
+        assert_eq!(
+            server,
+            Server {
+                host: "10.0.0.1".to_string(),
+                port: 8080,
+                secure: true,
+                ratio: Some(0.5),
+                tags: vec!["1".to_string(), "two".to_string()],
+            }
+        );
+    }
+}
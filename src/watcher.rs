@@ -1,7 +1,9 @@
+use crate::loader::LoadOptions;
+use crate::validator::ValidationError;
 use crate::{ConfigError, ConfigResult};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -16,6 +18,69 @@ pub struct ConfigData<T> {
     pub data: T,
     pub last_modified: std::time::SystemTime,
     pub version: u64,
+    /// Warnings of the load that produced `data`
+    pub warnings: Vec<ValidationError>,
+    /// Secret files `data` was resolved from, watched along with the config
+    pub sources: Vec<PathBuf>,
+}
+
+/// Event sent to [`Config::watch_events`](crate::Config::watch_events) subscribers
+#[derive(Debug, Clone)]
+pub enum ConfigEvent<T> {
+    /// The config was reloaded, warnings of the reload come first
+    Reloaded(T),
+    /// A warning for the user, e.g. a deprecated key
+    Warning(ValidationError),
+}
+
+/// Channels reloaded configs are published on
+#[derive(Debug)]
+pub struct ReloadSender<T> {
+    changes: broadcast::Sender<T>,
+    events: broadcast::Sender<ConfigEvent<T>>,
+}
+
+impl<T: Clone> ReloadSender<T> {
+    pub fn new() -> Self {
+        Self {
+            changes: broadcast::channel(100).0,
+            events: broadcast::channel(100).0,
+        }
+    }
+
+    /// Subscribe to reloaded configs
+    pub fn subscribe(&self) -> broadcast::Receiver<T> {
+        self.changes.subscribe()
+    }
+
+    /// Subscribe to reloads and warnings
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConfigEvent<T>> {
+        self.events.subscribe()
+    }
+
+    /// Publish a reloaded config and the warnings of its load
+    pub fn publish(&self, data: T, warnings: &[ValidationError]) {
+        for warning in warnings {
+            let _ = self.events.send(ConfigEvent::Warning(warning.clone()));
+        }
+        let _ = self.events.send(ConfigEvent::Reloaded(data.clone()));
+        let _ = self.changes.send(data);
+    }
+}
+
+impl<T: Clone> Default for ReloadSender<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ReloadSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            changes: self.changes.clone(),
+            events: self.events.clone(),
+        }
+    }
 }
 
 /// Start a file watcher for hot-reload
@@ -24,47 +89,54 @@ pub async fn start_watcher<T>(
     file_path: PathBuf,
     _watcher_id: Uuid,
     config_data: Arc<RwLock<ConfigData<T>>>,
-    reload_tx: broadcast::Sender<T>,
+    reload_tx: ReloadSender<T>,
+    options: Arc<LoadOptions>,
 ) -> ConfigResult<()>
 where
     T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
 {
     use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 
+    // Events carry absolute paths, so relative paths like `.env` must be resolved first
+    let file_path = tokio::fs::canonicalize(&file_path).await?;
+    let watch_dir = file_path
+        .parent()
+        .map(PathBuf::from)
+        .ok_or_else(|| ConfigError::InvalidPath(file_path.to_string_lossy().to_string()))?;
+
     let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-    let file_path_clone = file_path.clone();
-
-    // Start the watcher in a separate thread
-    std::thread::spawn(move || {
-        let (notify_tx, notify_rx) = std::sync::mpsc::channel();
-        let mut watcher = RecommendedWatcher::new(notify_tx, notify::Config::default())
-            .expect("Watcher could not be created");
-
-        // Watch the config file
-        if let Err(e) = watcher.watch(&file_path_clone, RecursiveMode::NonRecursive) {
-            eprintln!("Error watching file: {e:?}");
-            return;
-        }
 
-        // Wait for notifications
-        for res in notify_rx {
-            match res {
-                Ok(event) => {
-                    if let Err(e) = tx.blocking_send(event) {
-                        eprintln!("Error sending notification: {e:?}");
-                    }
+    // notify calls the handler from its own thread
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if let Err(e) = tx.blocking_send(event) {
+                    eprintln!("Error sending notification: {e:?}");
                 }
-                Err(e) => eprintln!("Watcher error: {e:?}"),
             }
-        }
-    });
-
-    // Process notifications asynchronously
+            Err(e) => eprintln!("Watcher error: {e:?}"),
+        },
+        notify::Config::default(),
+    )
+    .map_err(|e| ConfigError::HotReload(format!("Watcher could not be created: {e}")))?;
+
+    // Watch the parent directory so files replaced by editors (rename) keep being tracked
+    watcher
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| ConfigError::HotReload(format!("Error watching file: {e}")))?;
+
+    // Secret files referenced by the config are watched as well
+    let mut sources = SecretSources::new(watch_dir);
+    let files = config_data.read().unwrap().sources.clone();
+    sources.update(&mut watcher, files).await;
+
+    // Process notifications asynchronously, the watcher lives as long as the task
     tokio::spawn(async move {
         while let Some(event) = rx.recv().await {
-            if should_reload(&event, &file_path) {
-                if let Err(e) = handle_file_change(&file_path, &config_data, &reload_tx).await {
-                    eprintln!("Error reloading config: {e:?}");
+            if should_reload(&event, &file_path, &sources) {
+                match handle_file_change(&file_path, &config_data, &reload_tx, &options).await {
+                    Ok(files) => sources.update(&mut watcher, files).await,
+                    Err(e) => eprintln!("Error reloading config: {e:?}"),
                 }
             }
         }
@@ -73,12 +145,76 @@ where
     Ok(())
 }
 
+/// Secret files referenced by a watched config
+#[cfg(feature = "hot-reload")]
+struct SecretSources {
+    /// Directories the watcher already observes
+    watched_dirs: std::collections::HashSet<PathBuf>,
+    /// Absolute paths of the referenced files
+    files: std::collections::HashSet<PathBuf>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl SecretSources {
+    fn new(config_dir: PathBuf) -> Self {
+        Self {
+            watched_dirs: [config_dir].into_iter().collect(),
+            files: std::collections::HashSet::new(),
+        }
+    }
+
+    /// Track the files referenced after the latest load
+    ///
+    /// Only the directory is canonicalized: mounted secrets are often
+    /// symlinks that are swapped on update.
+    async fn update(&mut self, watcher: &mut notify::RecommendedWatcher, files: Vec<PathBuf>) {
+        use notify::{RecursiveMode, Watcher};
+
+        self.files.clear();
+        for file in files {
+            let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+                continue;
+            };
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            let Ok(dir) = tokio::fs::canonicalize(dir).await else {
+                continue;
+            };
+
+            if !self.watched_dirs.contains(&dir) {
+                if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                    eprintln!("Error watching secret file: {e:?}");
+                    continue;
+                }
+                self.watched_dirs.insert(dir.clone());
+            }
+            self.files.insert(dir.join(name));
+        }
+    }
+
+    /// Check if a path is a referenced file, or a `..`-prefixed entry next to
+    /// one, which Kubernetes swaps to update mounted secrets atomically
+    fn affects(&self, path: &Path) -> bool {
+        if self.files.contains(path) {
+            return true;
+        }
+        let swapped = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(".."));
+        swapped && self.files.iter().any(|file| file.parent() == path.parent())
+    }
+}
+
 #[cfg(not(feature = "hot-reload"))]
 pub async fn start_watcher<T>(
     _file_path: PathBuf,
     _watcher_id: Uuid,
     _config_data: Arc<RwLock<ConfigData<T>>>,
-    _reload_tx: broadcast::Sender<T>,
+    _reload_tx: ReloadSender<T>,
+    _options: Arc<LoadOptions>,
 ) -> ConfigResult<()>
 where
     T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
@@ -90,43 +226,52 @@ where
 
 /// Check if a file change should trigger a reload
 #[cfg(feature = "hot-reload")]
-fn should_reload(event: &notify::Event, file_path: &PathBuf) -> bool {
+fn should_reload(event: &notify::Event, file_path: &PathBuf, sources: &SecretSources) -> bool {
     matches!(
         event.kind,
         notify::EventKind::Modify(ModifyKind::Data(_))
             | notify::EventKind::Modify(ModifyKind::Metadata(_))
+            | notify::EventKind::Modify(ModifyKind::Name(_))
             | notify::EventKind::Create(_)
-    ) && event.paths.iter().any(|path| path == file_path)
+    ) && event
+        .paths
+        .iter()
+        .any(|path| path == file_path || sources.affects(path))
 }
 
 /// Handle a file change
+///
+/// Returns the secret files referenced by the new config.
 #[allow(dead_code)]
 async fn handle_file_change<T>(
-    file_path: &std::path::Path,
+    file_path: &Path,
     config_data: &Arc<RwLock<ConfigData<T>>>,
-    reload_tx: &broadcast::Sender<T>,
-) -> ConfigResult<()>
+    reload_tx: &ReloadSender<T>,
+    options: &LoadOptions,
+) -> ConfigResult<Vec<PathBuf>>
 where
     T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
 {
     // Wait a bit to ensure the file is fully written
     tokio::time::sleep(Duration::from_millis(100)).await;
 
-    // Load the new config
-    let new_data: T = crate::loader::load_from_file(file_path).await?;
+    // Load the new config, resolving secret references again
+    let loaded = crate::loader::load_with_sources::<T>(file_path, options).await?;
 
     // Update the config data
     {
         let mut data = config_data.write().unwrap();
-        data.data = new_data.clone();
+        data.data = loaded.data.clone();
         data.last_modified = std::time::SystemTime::now();
         data.version += 1;
+        data.warnings = loaded.warnings.clone();
+        data.sources = loaded.sources.clone();
     }
 
     // Notify all listeners
-    let _ = reload_tx.send(new_data);
+    reload_tx.publish(loaded.data, &loaded.warnings);
 
-    Ok(())
+    Ok(loaded.sources)
 }
 
 /// Watcher manager for multiple files
@@ -150,7 +295,8 @@ impl ConfigWatcherManager {
         _file_path: PathBuf,
         _watcher_id: Uuid,
         _config_data: Arc<RwLock<ConfigData<T>>>,
-        _reload_tx: broadcast::Sender<T>,
+        _reload_tx: ReloadSender<T>,
+        _options: Arc<LoadOptions>,
     ) -> ConfigResult<()>
     where
         T: Clone + DeserializeOwned + Serialize + Send + Sync + 'static,
@@ -159,7 +305,7 @@ impl ConfigWatcherManager {
         {
             let handle = tokio::spawn(async move {
                 if let Err(e) =
-                    start_watcher(_file_path, _watcher_id, _config_data, _reload_tx).await
+                    start_watcher(_file_path, _watcher_id, _config_data, _reload_tx, _options).await
                 {
                     eprintln!("Error starting watcher: {e:?}");
                 }
@@ -286,12 +432,20 @@ mod tests {
             },
             last_modified: std::time::SystemTime::now(),
             version: 1,
+            warnings: Vec::new(),
+            sources: Vec::new(),
         }));
-        let (reload_tx, _) = broadcast::channel(100);
+        let reload_tx = ReloadSender::new();
 
         // Add watcher (only if hot-reload feature is enabled)
         let result = manager
-            .add_watcher(path, watcher_id, config_data, reload_tx)
+            .add_watcher(
+                path,
+                watcher_id,
+                config_data,
+                reload_tx,
+                Arc::new(LoadOptions::default()),
+            )
             .await;
 
         #[cfg(feature = "hot-reload")]
@@ -310,6 +464,101 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    #[cfg(all(feature = "hot-reload", feature = "dotenv"))]
+    async fn test_watcher_reloads_dotenv() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(".env");
+        tokio::fs::write(&path, "VALUE=initial\n").await.unwrap();
+
+        let config = crate::Config::<TestConfig>::from_file_with_watcher(&path)
+            .await
+            .unwrap();
+        let mut changes = config.watch_changes();
+
+        // Give the watcher thread time to register
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        tokio::fs::write(&path, "VALUE=changed\n").await.unwrap();
+
+        let changed = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("no reload event")
+            .unwrap();
+        assert_eq!(changed.value, "changed");
+        assert_eq!(config.get().value, "changed");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "hot-reload")]
+    async fn test_watcher_reresolves_secret_files() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let secret_dir = tempfile::tempdir().unwrap();
+        let secret = secret_dir.path().join("value");
+        tokio::fs::write(&secret, "initial\n").await.unwrap();
+        let path = config_dir.path().join("config.json");
+        let content = serde_json::json!({ "value": format!("file://{}", secret.display()) });
+        tokio::fs::write(&path, content.to_string()).await.unwrap();
+
+        let config = crate::ConfigBuilder::new()
+            .file(&path)
+            .resolve_secrets(true)
+            .hot_reload(true)
+            .build::<TestConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().value, "initial");
+        let mut changes = config.watch_changes();
+
+        // Give the watcher thread time to register
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        tokio::fs::write(&secret, "rotated\n").await.unwrap();
+
+        let changed = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("no reload event")
+            .unwrap();
+        assert_eq!(changed.value, "rotated");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "hot-reload")]
+    async fn test_watcher_start_does_not_load_again() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static RESOLVED: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct CountingResolver;
+
+        #[async_trait::async_trait]
+        impl crate::resolver::SecretResolver for CountingResolver {
+            fn scheme(&self) -> &str {
+                "count"
+            }
+
+            async fn resolve(&self, reference: &str) -> ConfigResult<String> {
+                RESOLVED.fetch_add(1, Ordering::SeqCst);
+                Ok(reference.to_string())
+            }
+        }
+
+        let config_dir = tempfile::tempdir().unwrap();
+        let path = config_dir.path().join("config.json");
+        tokio::fs::write(&path, r#"{"value": "count://initial"}"#)
+            .await
+            .unwrap();
+
+        let config = crate::ConfigBuilder::new()
+            .file(&path)
+            .secret_resolver(CountingResolver)
+            .hot_reload(true)
+            .build::<TestConfig>()
+            .await
+            .unwrap();
+        assert_eq!(config.get().value, "initial");
+        assert_eq!(RESOLVED.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn test_file_utils() {
         let temp_file = NamedTempFile::new().unwrap();
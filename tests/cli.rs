@@ -0,0 +1,209 @@
+//! Tests for the `rusty-config` binary
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rusty-config"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn code(output: &Output) -> i32 {
+    output.status.code().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn arg(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn test_validate() {
+    let dir = TempDir::new().unwrap();
+    let config = write(&dir, "app.yaml", "port: 8080\n");
+    let schema = write(
+        &dir,
+        "app.schema.json",
+        r#"{"type": "object", "properties": {"port": {"type": "integer", "maximum": 1024}}}"#,
+    );
+
+    let output = run(&["validate", arg(&config)]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert!(stdout(&output).ends_with(": ok\n"));
+
+    let output = run(&["validate", arg(&config), "--schema", arg(&schema)]);
+    assert_eq!(code(&output), 1);
+    assert!(stderr(&output).contains("port"), "{}", stderr(&output));
+
+    let output = run(&["validate", arg(&dir.path().join("missing.yaml"))]);
+    assert_eq!(code(&output), 2);
+}
+
+#[test]
+fn test_convert() {
+    let dir = TempDir::new().unwrap();
+    let input = write(&dir, "app.yaml", "name: app\nproxy: ~\n");
+    let output_path = dir.path().join("app.toml");
+
+    let output = run(&["convert", arg(&input), arg(&output_path)]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert!(stderr(&output).contains("proxy: TOML has no null"));
+    assert_eq!(
+        std::fs::read_to_string(&output_path).unwrap(),
+        "name = \"app\"\n"
+    );
+
+    let strict_path = dir.path().join("strict.toml");
+    let output = run(&["convert", "--strict", arg(&input), arg(&strict_path)]);
+    assert_eq!(code(&output), 1);
+    assert!(!strict_path.exists());
+
+    let list = write(&dir, "list.yaml", "- a\n");
+    let list_path = dir.path().join("list.toml");
+    let output = run(&["convert", arg(&list), arg(&list_path)]);
+    assert_eq!(code(&output), 1);
+    assert!(!list_path.exists());
+}
+
+#[test]
+fn test_get() {
+    let dir = TempDir::new().unwrap();
+    let config = write(
+        &dir,
+        "app.yaml",
+        "server:\n  host: localhost\n  port: 8080\n",
+    );
+
+    let output = run(&["get", arg(&config), "server.host"]);
+    assert_eq!(
+        (code(&output), stdout(&output).as_str()),
+        (0, "localhost\n")
+    );
+    let output = run(&["get", arg(&config), "server.port"]);
+    assert_eq!((code(&output), stdout(&output).as_str()), (0, "8080\n"));
+    let output = run(&["get", arg(&config), "server.tls"]);
+    assert_eq!(code(&output), 1);
+}
+
+#[test]
+fn test_set_keeps_comments() {
+    let dir = TempDir::new().unwrap();
+    let config = write(&dir, "app.yaml", "server:\n  port: 80 # http\n");
+
+    let output = run(&["set", arg(&config), "server.port=8080", "debug=true"]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(
+        std::fs::read_to_string(&config).unwrap(),
+        "server:\n  port: 8080 # http\ndebug: true\n"
+    );
+
+    let output = run(&["set", arg(&config), "no-equals"]);
+    assert_eq!(code(&output), 2);
+}
+
+#[test]
+fn test_diff_across_formats() {
+    let dir = TempDir::new().unwrap();
+    let yaml = write(&dir, "app.yaml", "port: 8080\ndebug: true\n");
+    let properties = write(&dir, "app.properties", "port=8080\ndebug=true\n");
+    let json = write(&dir, "app.json", r#"{"debug": true, "port": 9090}"#);
+
+    let output = run(&["diff", arg(&yaml), arg(&properties)]);
+    assert_eq!(code(&output), 0, "{}", stdout(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = run(&["diff", arg(&yaml), arg(&json)]);
+    assert_eq!(code(&output), 1);
+    assert_eq!(stdout(&output), "~ port: 8080 -> 9090\n");
+}
+
+#[test]
+fn test_fmt() {
+    let dir = TempDir::new().unwrap();
+    let plain = write(&dir, "plain.json", r#"{"a":1}"#);
+    let commented = write(&dir, "commented.yaml", "# important\na:   1\n");
+
+    let output = run(&["fmt", "--check", arg(&plain)]);
+    assert_eq!(code(&output), 1);
+    let output = run(&["fmt", arg(&plain)]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&plain).unwrap(), "{\n  \"a\": 1\n}");
+    let output = run(&["fmt", "--check", arg(&plain)]);
+    assert_eq!(code(&output), 0);
+
+    let output = run(&["fmt", arg(&commented)]);
+    assert_eq!(code(&output), 1);
+    assert!(stderr(&output).contains("--force"));
+    assert_eq!(
+        std::fs::read_to_string(&commented).unwrap(),
+        "# important\na:   1\n"
+    );
+    let output = run(&["fmt", "--force", arg(&commented)]);
+    assert_eq!(code(&output), 0);
+    assert_eq!(std::fs::read_to_string(&commented).unwrap(), "a: 1\n");
+}
+
+#[test]
+fn test_merge() {
+    let dir = TempDir::new().unwrap();
+    let base = write(&dir, "base.yaml", "server:\n  host: a\n  port: 80\n");
+    let overlay = write(&dir, "overlay.json", r#"{"server": {"port": 8080}}"#);
+
+    let output = run(&["merge", arg(&base), arg(&overlay)]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(stdout(&output), "server:\n  host: a\n  port: 8080\n");
+
+    let merged = dir.path().join("merged.toml");
+    let output = run(&["merge", arg(&base), arg(&overlay), "-o", arg(&merged)]);
+    assert_eq!(code(&output), 0);
+    assert_eq!(
+        std::fs::read_to_string(&merged).unwrap(),
+        "[server]\nhost = \"a\"\nport = 8080\n"
+    );
+}
+
+#[cfg(feature = "sops")]
+#[test]
+fn test_sops_files_are_not_rewritten() {
+    let dir = TempDir::new().unwrap();
+    let original = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/sops/secrets.age.yaml"
+    ))
+    .unwrap();
+    let secrets = write(&dir, "secrets.yaml", &original);
+    let plain = write(&dir, "plain.yaml", "a: 1\n");
+
+    let output = run(&["set", arg(&secrets), "db.password=x"]);
+    assert_eq!(code(&output), 2);
+    assert!(stderr(&output).contains("SOPS"), "{}", stderr(&output));
+
+    let output = run(&["fmt", "--force", arg(&secrets)]);
+    assert_eq!(code(&output), 1);
+    assert!(stderr(&output).contains("SOPS"), "{}", stderr(&output));
+
+    for args in [
+        vec!["convert", arg(&plain), arg(&secrets)],
+        vec!["merge", arg(&plain), "-o", arg(&secrets)],
+    ] {
+        let output = run(&args);
+        assert_eq!(code(&output), 2, "{args:?}");
+    }
+    assert_eq!(std::fs::read_to_string(&secrets).unwrap(), original);
+}